@@ -0,0 +1,59 @@
+use std::cell::{Cell, OnceCell};
+
+/// Append-only storage that hands out shared references, which stay valid while more values get
+/// added since values are never moved or removed until [Self] is dropped.
+///
+/// Values are stored in chunks that double in size, so chunk `i` holds `2^i` values.
+pub struct Arena<T> {
+    chunks: [OnceCell<Box<[OnceCell<T>]>>; usize::BITS as usize],
+    len: Cell<usize>,
+}
+impl<T> Arena<T> {
+    pub const fn new() -> Self {
+        Self {
+            chunks: [const { OnceCell::new() }; usize::BITS as usize],
+            len: Cell::new(0),
+        }
+    }
+
+    /// Store `value` and return a reference to it.
+    pub fn alloc(&self, value: T) -> &T {
+        // counted from 1, so that chunk `i` starts at `2^i`
+        let index = self.len.get() + 1;
+        self.len.set(index);
+
+        let chunk = index.ilog2();
+        self.chunks[chunk as usize]
+            .get_or_init(|| (0..1 << chunk).map(|_| OnceCell::new()).collect())
+            [index - (1 << chunk)]
+            .get_or_init(move || value)
+    }
+
+    /// Every value in the order that it was stored.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks
+            .iter()
+            .map_while(OnceCell::get)
+            .flat_map(|chunk| chunk.iter())
+            .map_while(OnceCell::get)
+    }
+}
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_alloc() {
+        let arena = Arena::new();
+        let values = (0..100).map(|i| arena.alloc(i)).collect::<Vec<_>>();
+
+        assert!(values.into_iter().copied().eq(0..100));
+        assert!(arena.iter().copied().eq(0..100));
+    }
+}
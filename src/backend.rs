@@ -2,7 +2,10 @@
 pub mod windows;
 
 use {
-    crate::state::{EventSender, Storm},
+    crate::{
+        config::key::{KeyModifier, KeySequence, KeySide},
+        state::{EventSender, Storm},
+    },
     std::{collections::HashMap, fmt::Display},
 };
 
@@ -14,12 +17,25 @@ where
     /// This function gets called whenever [Storm] receives an event. Useful for things
     /// that need to occur every event.
     fn each_event(_: &mut Storm<Self, W, E>) {}
+    /// Send keys to the focused window as if they were typed, without them being reported back
+    /// as [crate::state::Event::Key].
+    fn inject_keys(&mut self, _: &KeySequence<'_>) -> Result<(), E>;
+    /// Release a modifier that was tapped to execute a key binding, whose release was blocked, so
+    /// that the platform does not act on the tap as well (e.g. open the start menu for super).
+    fn release_tapped(&mut self, _: KeyModifier, _: KeySide) -> Result<(), E>;
     /// Operate on windows before they get put into [Storm].
     fn new(_: &mut HashMap<u8, Vec<W>>, _: EventSender<W, E>) -> Result<Self, E>;
+    /// The part of the screen that windows can be tiled in.
+    fn screen_area(&self) -> Result<Rect, E>;
+    /// Where the pointer is on the screen.
+    fn pointer(&self) -> Result<(i16, i16), E>;
+    /// Set whether pointer motion gets reported as [crate::state::Event::Pointer], which [Storm]
+    /// only needs while a window is grabbed.
+    fn track_pointer(&mut self, _: bool) {}
 }
 
-pub trait Window {
-    type Error;
+pub trait Window: PartialEq {
+    type Error: Display;
     type String;
 
     fn is_alive(&self) -> bool;
@@ -37,9 +53,42 @@ pub trait Window {
     fn set_visibility(&mut self, _: bool) -> Result<(), Self::Error>;
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Rect {
     x: i16,
     y: i16,
     width: u16,
     height: u16,
 }
+impl Rect {
+    pub const fn new(x: i16, y: i16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub const fn x(&self) -> i16 {
+        self.x
+    }
+    pub const fn y(&self) -> i16 {
+        self.y
+    }
+    pub const fn width(&self) -> u16 {
+        self.width
+    }
+    pub const fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Whether the point at `(x, y)` is inside of [self].
+    pub fn contains(&self, (x, y): (i16, i16)) -> bool {
+        let inside = |point: i16, start: i16, length: u16| {
+            (i32::from(start)..i32::from(start) + i32::from(length)).contains(&i32::from(point))
+        };
+
+        inside(x, self.x, self.width) && inside(y, self.y, self.height)
+    }
+}
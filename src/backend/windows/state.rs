@@ -1,9 +1,10 @@
 use {
     crate::{
         backend::{
-            State,
+            Rect, State,
             windows::{WinapiError, WindowsBackendError, WindowsWindow},
         },
+        config::key::{KeyModifier, KeySequence, KeySide},
         error,
         state::{Event, EventSender, Storm},
     },
@@ -11,20 +12,34 @@ use {
     std::{
         collections::HashMap,
         mem,
+        num::TryFromIntError,
         ptr::{NonNull, null_mut},
-        sync::atomic::AtomicPtr,
+        sync::atomic::{AtomicPtr, Ordering},
         thread,
     },
     winapi::{
-        shared::windef::HHOOK__,
-        um::winuser::{
-            DispatchMessageW, GetForegroundWindow, GetMessageW, SetWindowsHookExW,
-            TranslateMessage, UnhookWindowsHookEx, WH_KEYBOARD_LL,
+        ctypes::c_int,
+        shared::{
+            minwindef::{LPARAM, LRESULT, WPARAM},
+            windef::{HHOOK__, POINT, RECT},
+        },
+        um::{
+            winnt::PVOID,
+            winuser::{
+                DispatchMessageW, GetCursorPos, GetForegroundWindow, GetMessageW, SPI_GETWORKAREA,
+                SetWindowsHookExW, SystemParametersInfoW, TranslateMessage, UnhookWindowsHookEx,
+                WH_KEYBOARD_LL, WH_MOUSE_LL,
+            },
         },
     },
 };
 
 mod key_hook;
+mod key_inject;
+mod mouse_hook;
+mod virt_key;
+
+type HookProc = unsafe extern "system" fn(c_int, WPARAM, LPARAM) -> LRESULT;
 
 static EVENT_SENDER: RwLock<Option<EventSender<WindowsWindow, WindowsBackendError>>> =
     const_rwlock(None);
@@ -32,11 +47,13 @@ static EVENT_SENDER: RwLock<Option<EventSender<WindowsWindow, WindowsBackendErro
 pub struct WindowsBackendState {
     event_sender: EventSender<WindowsWindow, WindowsBackendError>,
     key_hook: NonNull<HHOOK__>,
+    mouse_hook: NonNull<HHOOK__>,
 }
 impl Drop for WindowsBackendState {
     fn drop(&mut self) {
         unsafe {
             UnhookWindowsHookEx(self.key_hook.as_ptr());
+            UnhookWindowsHookEx(self.mouse_hook.as_ptr());
         }
         *EVENT_SENDER.write() = None;
     }
@@ -54,6 +71,41 @@ impl State<WindowsWindow, WindowsBackendError> for WindowsBackendState {
         }
     }
 
+    fn inject_keys(&mut self, keys: &KeySequence<'_>) -> Result<(), WindowsBackendError> {
+        key_inject::inject_keys(keys)
+    }
+
+    fn release_tapped(
+        &mut self,
+        modifier: KeyModifier,
+        side: KeySide,
+    ) -> Result<(), WindowsBackendError> {
+        key_inject::release_tapped(modifier, side)
+    }
+
+    fn screen_area(&self) -> Result<Rect, WindowsBackendError> {
+        // SAFETY: The rect is initialized with [SystemParametersInfoW].
+        let mut rect: RECT = unsafe { mem::zeroed() };
+
+        // the work area leaves out the task bar
+        WinapiError::from_return(unsafe {
+            SystemParametersInfoW(SPI_GETWORKAREA, 0, &mut rect as *mut _ as PVOID, 0)
+        })?;
+        rect.try_into()
+            .map_err(<TryFromIntError as Into<WindowsBackendError>>::into)
+    }
+
+    fn pointer(&self) -> Result<(i16, i16), WindowsBackendError> {
+        let mut point = POINT { x: 0, y: 0 };
+        WinapiError::from_return(unsafe { GetCursorPos(&mut point) })?;
+
+        Ok((point.x.try_into()?, point.y.try_into()?))
+    }
+
+    fn track_pointer(&mut self, tracking: bool) {
+        mouse_hook::TRACK_POINTER.store(tracking, Ordering::Relaxed);
+    }
+
     fn new(
         _: &mut HashMap<u8, Vec<WindowsWindow>>,
         event_sender: EventSender<WindowsWindow, WindowsBackendError>,
@@ -70,14 +122,25 @@ impl State<WindowsWindow, WindowsBackendError> for WindowsBackendState {
         let (tx, rx) = oneshot::channel();
 
         thread::spawn(move || {
-            // the hook must be set on the same thread as the message sending
-            let _ = tx.send(
+            // the hooks must be set on the same thread as the message sending
+            let hook = |id, hook: HookProc| {
                 WinapiError::from_return(unsafe {
-                    SetWindowsHookExW(WH_KEYBOARD_LL, Some(key_hook::key_hook), null_mut(), 0)
+                    SetWindowsHookExW(id, Some(hook), null_mut(), 0)
                 })
                 .map(NonNull::as_ptr)
-                .map(AtomicPtr::new),
-            );
+                .map(AtomicPtr::new)
+            };
+            let _ = tx.send(hook(WH_KEYBOARD_LL, key_hook::key_hook).and_then(
+                |key_hook| match hook(WH_MOUSE_LL, mouse_hook::mouse_hook) {
+                    Ok(mouse_hook) => Ok([key_hook, mouse_hook]),
+                    Err(err) => {
+                        unsafe {
+                            UnhookWindowsHookEx(key_hook.into_inner());
+                        }
+                        Err(err)
+                    }
+                },
+            ));
 
             let mut msg = unsafe { mem::zeroed() };
             loop {
@@ -93,18 +156,15 @@ impl State<WindowsWindow, WindowsBackendError> for WindowsBackendState {
             }
         });
 
+        let [key_hook, mouse_hook] = rx.recv().expect(error::CLOSED_CHANNEL)?.map(|hook| {
+            NonNull::new(hook.into_inner())
+                .expect("internal error: [WinapiError::from_return] should filter null pointers")
+        });
+
         Ok(Self {
             event_sender,
-            key_hook: rx
-                .recv()
-                .expect(error::CLOSED_CHANNEL)
-                .map(AtomicPtr::into_inner)
-                .map(NonNull::new)
-                .map(|ptr| {
-                    ptr.expect(
-                        "internal error: [WinapiError::from_return] should filter null pointers",
-                    )
-                })?,
+            key_hook,
+            mouse_hook,
         })
     }
 }
@@ -1,64 +1,71 @@
 use {
-    super::EVENT_SENDER,
+    super::{EVENT_SENDER, key_inject, virt_key},
     crate::{
         backend::windows::{WinapiError, WindowsBackendError},
-        config::key::{InvisibleKey, Key, KeyKind, KeyModifier, KeyModifiers},
-        state::{KeyIntercept, Event},
+        config::key::{InvisibleKey, Key, KeyKind, KeyModifier, KeyModifiers, KeySide, ScanCode},
+        state::{Event, KeyIntercept, KeyState},
     },
+    parking_lot::{Mutex, const_mutex},
     std::{borrow::Cow, num::NonZeroUsize, ptr::null_mut},
     widestring::ustr::U16Str,
     winapi::{
         ctypes::c_int,
-        shared::minwindef::{LPARAM, LRESULT, WPARAM},
+        shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM},
         um::winuser::{
-            CallNextHookEx, GetKeyState, GetKeyboardState, KBDLLHOOKSTRUCT, ToUnicode, VK_CONTROL,
-            VK_F1, VK_F24, VK_LWIN, VK_MENU, VK_NEXT, VK_PRIOR, VK_RWIN, VK_SHIFT, WM_KEYDOWN,
+            CallNextHookEx, GetKeyState, GetKeyboardState, KBDLLHOOKSTRUCT, LLKHF_EXTENDED,
+            ToUnicode, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_RCONTROL, VK_RMENU, VK_RSHIFT,
+            VK_RWIN, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
         },
     },
 };
 
-/// Returns Ok(None) for dead keys.
-fn translate_key(key_diff: LPARAM) -> Result<Option<Key<'static>>, WindowsBackendError> {
-    let key_diff = unsafe { (key_diff as *mut KBDLLHOOKSTRUCT).as_ref() }
-        .ok_or(WindowsBackendError::NullKbdllhookstruct)?;
+/// What the keys that are held down were translated into by their virtual key, so that repeats
+/// can be told apart from presses and releases do not have to be translated again.
+static HELD_KEYS: Mutex<[Option<KeyKind<'static>>; 256]> = const_mutex([const { None }; 256]);
 
-    let modifiers = [
-        (KeyModifier::Alt, &[VK_MENU] as &[_]),
-        (KeyModifier::Control, &[VK_CONTROL]),
-        (KeyModifier::Shift, &[VK_SHIFT]),
-        (KeyModifier::Super, &[VK_LWIN, VK_RWIN]),
+/// The modifiers that are held down right now.
+pub fn modifiers() -> KeyModifiers {
+    [
+        (KeyModifier::Alt, VK_LMENU, VK_RMENU),
+        (KeyModifier::Control, VK_LCONTROL, VK_RCONTROL),
+        (KeyModifier::Shift, VK_LSHIFT, VK_RSHIFT),
+        (KeyModifier::Super, VK_LWIN, VK_RWIN),
     ]
     .into_iter()
-    .map(|(modifier, virt_keys)| {
-        (
-            modifier,
-            virt_keys
-                .iter()
-                .copied()
-                .map(|virt_key| unsafe { GetKeyState(virt_key) })
-                .any(|virt_key| virt_key & (1 << 15) != 0),
-        )
+    .filter_map(|(modifier, left, right)| {
+        let down = |virt_key| unsafe { GetKeyState(virt_key) } & (1 << 15) != 0;
+
+        match (down(left), down(right)) {
+            (true, false) => Some((modifier, KeySide::Left)),
+            (false, true) => Some((modifier, KeySide::Right)),
+            (true, true) => Some((modifier, KeySide::Any)),
+            (false, false) => None,
+        }
     })
-    .collect::<KeyModifiers>();
-
-    match key_diff.vkCode as i32 {
-        key @ VK_F1..=VK_F24 => Ok(Some(Key::new(
-            modifiers,
-            KeyKind::Invisible(InvisibleKey::F(
-                (key + 1 - VK_F1)
-                    .try_into()
-                    .expect("internal error: the pattern should ensure this is valid"),
-            )),
-        ))),
-        VK_PRIOR => Ok(Some(Key::new(
-            modifiers,
-            KeyKind::Invisible(InvisibleKey::PageUp),
-        ))),
-        VK_NEXT => Ok(Some(Key::new(
-            modifiers,
-            KeyKind::Invisible(InvisibleKey::PageDown),
-        ))),
-        _ => {
+    .collect()
+}
+
+/// The modifiers that have been pressed and not released yet according to [HELD_KEYS].
+pub fn held_modifiers() -> KeyModifiers {
+    HELD_KEYS
+        .lock()
+        .iter()
+        .filter_map(|kind| match kind {
+            Some(KeyKind::Invisible(InvisibleKey::Modifier(modifier, side))) => {
+                Some((*modifier, *side))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns Ok(None) for dead keys.
+fn translate_kind(
+    key_diff: &KBDLLHOOKSTRUCT,
+) -> Result<Option<KeyKind<'static>>, WindowsBackendError> {
+    match virt_key::to_invisible(key_diff.vkCode as c_int) {
+        Some(key) => Ok(Some(KeyKind::Invisible(key))),
+        None => {
             let mut keyboard_state = [0; 256];
             WinapiError::from_return(unsafe { GetKeyboardState(keyboard_state.as_mut_ptr()) })?;
 
@@ -81,16 +88,51 @@ fn translate_key(key_diff: LPARAM) -> Result<Option<Key<'static>>, WindowsBacken
                 return Ok(None);
             };
 
-            Ok(Some(Key::new(
-                modifiers,
-                KeyKind::Visible(Cow::Owned(
-                    U16Str::from_slice(&buffer[..len.get()]).to_string_lossy(),
-                )),
-            )))
+            Ok(Some(KeyKind::Visible(Cow::Owned(
+                U16Str::from_slice(&buffer[..len.get()]).to_string_lossy(),
+            ))))
         }
     }
 }
 
+/// Returns Ok(None) for dead keys.
+fn translate_key(
+    key_diff: LPARAM,
+    released: bool,
+) -> Result<Option<(Key<'static>, KeyState, ScanCode)>, WindowsBackendError> {
+    let key_diff = unsafe { (key_diff as *mut KBDLLHOOKSTRUCT).as_ref() }
+        .ok_or(WindowsBackendError::NullKbdllhookstruct)?;
+
+    let modifiers = modifiers();
+
+    let mut held_keys = HELD_KEYS.lock();
+    let held = &mut held_keys[(key_diff.vkCode & 0xff) as usize];
+    let (kind, state) = if released {
+        match held.take() {
+            Some(kind) => (kind, KeyState::Released),
+            None => return Ok(None),
+        }
+    } else if let Some(kind) = held {
+        (kind.clone(), KeyState::Repeated)
+    } else {
+        match translate_kind(key_diff)? {
+            Some(kind) => (held.insert(kind).clone(), KeyState::Pressed),
+            None => return Ok(None),
+        }
+    };
+
+    let scan_code = match key_diff.flags & LLKHF_EXTENDED {
+        0 => key_diff.scanCode as u16,
+        _ => 0xe000 | key_diff.scanCode as u16,
+    };
+
+    Ok(Some((
+        Key::new(modifiers, kind),
+        state,
+        ScanCode(scan_code),
+    )))
+}
+
 pub unsafe extern "system" fn key_hook(
     code: c_int,
     event_ident: WPARAM,
@@ -101,26 +143,42 @@ pub unsafe extern "system" fn key_hook(
     if code < 0 {
         return call_next_hook();
     }
+    // keys replayed by [key_inject::inject_keys] were already processed
+    if unsafe { (key_diff as *const KBDLLHOOKSTRUCT).as_ref() }
+        .is_some_and(|key_diff| key_diff.dwExtraInfo == key_inject::INJECTED)
+    {
+        return call_next_hook();
+    }
 
-    if event_ident == WM_KEYDOWN as WPARAM {
-        if let Some(sender) = EVENT_SENDER.read().as_ref() {
-            let send = |event| {
-                drop(sender.send(event));
-            };
+    let released = match event_ident as UINT {
+        WM_KEYDOWN | WM_SYSKEYDOWN => Some(false),
+        WM_KEYUP | WM_SYSKEYUP => Some(true),
+        _ => None,
+    };
+    if let Some(released) = released
+        && let Some(sender) = EVENT_SENDER.read().as_ref()
+    {
+        let send = |event| {
+            drop(sender.send(event));
+        };
 
-            let (tx, rx) = oneshot::channel();
+        let (tx, rx) = oneshot::channel();
 
-            match translate_key(key_diff) {
-                Ok(Some(key)) => {
-                    send(Ok(Event::Key(tx, key)));
+        match translate_key(key_diff, released) {
+            Ok(Some((key, state, scan_code))) => {
+                send(Ok(Event::Key {
+                    consume: tx,
+                    key,
+                    state,
+                    scan_code: Some(scan_code),
+                }));
 
-                    if matches!(rx.recv().unwrap_or_default(), KeyIntercept::Block) {
-                        return 1;
-                    }
+                if matches!(rx.recv().unwrap_or_default(), KeyIntercept::Block) {
+                    return 1;
                 }
-                Ok(None) => {}
-                Err(err) => send(Err(err)),
             }
+            Ok(None) => {}
+            Err(err) => send(Err(err)),
         }
     }
 
@@ -0,0 +1,256 @@
+use {
+    super::{key_hook, virt_key},
+    crate::{
+        backend::windows::{WinapiError, WindowsBackendError},
+        config::key::{
+            KeyModifier, KeyModifiers, KeySequence, KeySide, KeyStroke, KeyStrokeKind, MouseKey,
+            ScanCode,
+        },
+    },
+    smallvec::SmallVec,
+    std::mem,
+    winapi::{
+        ctypes::c_int,
+        shared::{
+            basetsd::ULONG_PTR,
+            minwindef::{DWORD, WORD},
+        },
+        um::winuser::{
+            INPUT, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP,
+            KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE, MOUSEEVENTF_HWHEEL, MOUSEEVENTF_LEFTDOWN,
+            MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
+            MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_WHEEL, MOUSEEVENTF_XDOWN,
+            MOUSEEVENTF_XUP, MOUSEINPUT, SendInput, VkKeyScanW, WHEEL_DELTA, XBUTTON1, XBUTTON2,
+        },
+    },
+};
+
+/// Put in `dwExtraInfo` of injected keys, so that the keyboard hook can let them through.
+pub const INJECTED: ULONG_PTR = 0x5354_4f52;
+
+fn input(virt_key: WORD, scan: WORD, flags: DWORD) -> INPUT {
+    // SAFETY: [INPUT] is plain old data
+    let mut input: INPUT = unsafe { mem::zeroed() };
+    input.type_ = INPUT_KEYBOARD;
+    // SAFETY: the type is set to [INPUT_KEYBOARD] above
+    unsafe {
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: virt_key,
+            wScan: scan,
+            dwFlags: flags,
+            time: 0,
+            dwExtraInfo: INJECTED,
+        };
+    }
+
+    input
+}
+
+fn mouse_input(flags: DWORD, data: DWORD) -> INPUT {
+    // SAFETY: [INPUT] is plain old data
+    let mut input: INPUT = unsafe { mem::zeroed() };
+    input.type_ = INPUT_MOUSE;
+    // SAFETY: the type is set to [INPUT_MOUSE] above
+    unsafe {
+        *input.u.mi_mut() = MOUSEINPUT {
+            dx: 0,
+            dy: 0,
+            mouseData: data,
+            dwFlags: flags,
+            time: 0,
+            dwExtraInfo: INJECTED,
+        };
+    }
+
+    input
+}
+
+/// The virtual key that types `ch` on the current keyboard layout, along with the modifiers it
+/// needs, or none if it cannot be typed with a single key.
+fn char_virt_key(ch: char) -> Option<(WORD, KeyModifiers)> {
+    let mut buffer = [0; 2];
+    let [unit] = ch.encode_utf16(&mut buffer) else {
+        return None;
+    };
+
+    // SAFETY: takes no pointers
+    let scan = unsafe { VkKeyScanW(*unit) } as u16;
+    match scan {
+        0xffff => None,
+        scan => Some((
+            scan & 0xff,
+            [
+                (1 << 8, KeyModifier::Shift),
+                (2 << 8, KeyModifier::Control),
+                (4 << 8, KeyModifier::Alt),
+            ]
+            .into_iter()
+            .filter(|(bit, _)| scan & bit != 0)
+            .map(|(_, modifier)| (modifier, KeySide::Left))
+            .collect(),
+        )),
+    }
+}
+
+/// Append the inputs for pressing or releasing each of `modifiers`, skipping the ones in `held`
+/// so that modifiers that are physically held down stay down.
+fn push_modifiers<I>(
+    inputs: &mut SmallVec<[INPUT; 16]>,
+    modifiers: I,
+    held: KeyModifiers,
+    flags: DWORD,
+) where
+    I: IntoIterator<Item = (KeyModifier, KeySide)>,
+{
+    inputs.extend(
+        modifiers
+            .into_iter()
+            .filter(|(modifier, side)| {
+                !held.get(*modifier).is_some_and(|held| {
+                    held == KeySide::Any || *side == KeySide::Any || held == *side
+                })
+            })
+            .map(|(modifier, side)| {
+                input(
+                    virt_key::modifier_virt_key(modifier, side) as WORD,
+                    0,
+                    flags,
+                )
+            }),
+    );
+}
+
+/// Append the inputs for pressing and releasing `stroke` along with its modifiers.
+fn push_stroke(inputs: &mut SmallVec<[INPUT; 16]>, stroke: KeyStroke, held: KeyModifiers) {
+    let mods = stroke.mods();
+
+    push_modifiers(inputs, mods.iter_sided(), held, 0);
+    match stroke.kind() {
+        // a virtual key is used where possible, since applications only treat it as a shortcut
+        // along with the modifiers
+        KeyStrokeKind::Char(ch) => match char_virt_key(ch) {
+            Some((virt_key, needed)) => {
+                let extra = needed
+                    .iter_sided()
+                    .filter(|(modifier, _)| mods.get(*modifier).is_none())
+                    .collect::<SmallVec<[_; 3]>>();
+
+                push_modifiers(inputs, extra.iter().copied(), held, 0);
+                inputs.push(input(virt_key, 0, 0));
+                inputs.push(input(virt_key, 0, KEYEVENTF_KEYUP));
+                push_modifiers(inputs, extra, held, KEYEVENTF_KEYUP);
+            }
+            None => {
+                let mut push_char = |ch: char| {
+                    let mut buffer = [0; 2];
+                    ch.encode_utf16(&mut buffer).iter().for_each(|&unit| {
+                        inputs.push(input(0, unit, KEYEVENTF_UNICODE));
+                        inputs.push(input(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                    });
+                };
+
+                // unicode input ignores shift, and normalized keys store upper case letters as
+                // lower case letters with shift
+                match mods.get(KeyModifier::Shift) {
+                    Some(_) => ch.to_uppercase().for_each(push_char),
+                    None => push_char(ch),
+                }
+            }
+        },
+        KeyStrokeKind::Physical(ScanCode(code)) => {
+            let flags = match code & 0xe000 {
+                0xe000 => KEYEVENTF_SCANCODE | KEYEVENTF_EXTENDEDKEY,
+                _ => KEYEVENTF_SCANCODE,
+            };
+            let code = code & !0xe000;
+            inputs.push(input(0, code, flags));
+            inputs.push(input(0, code, flags | KEYEVENTF_KEYUP));
+        }
+        KeyStrokeKind::Mouse(key) => {
+            let wheel = |flags, delta: i32| mouse_input(flags, delta as DWORD);
+            match key {
+                MouseKey::Button(1) => inputs.extend([
+                    mouse_input(MOUSEEVENTF_LEFTDOWN, 0),
+                    mouse_input(MOUSEEVENTF_LEFTUP, 0),
+                ]),
+                MouseKey::Button(2) => inputs.extend([
+                    mouse_input(MOUSEEVENTF_MIDDLEDOWN, 0),
+                    mouse_input(MOUSEEVENTF_MIDDLEUP, 0),
+                ]),
+                MouseKey::Button(3) => inputs.extend([
+                    mouse_input(MOUSEEVENTF_RIGHTDOWN, 0),
+                    mouse_input(MOUSEEVENTF_RIGHTUP, 0),
+                ]),
+                MouseKey::Button(button @ (4 | 5)) => {
+                    let data = match button {
+                        4 => XBUTTON1,
+                        _ => XBUTTON2,
+                    } as DWORD;
+                    inputs.extend([
+                        mouse_input(MOUSEEVENTF_XDOWN, data),
+                        mouse_input(MOUSEEVENTF_XUP, data),
+                    ]);
+                }
+                // windows has no messages for other buttons
+                MouseKey::Button(_) => {}
+                MouseKey::WheelUp => inputs.push(wheel(MOUSEEVENTF_WHEEL, WHEEL_DELTA as i32)),
+                MouseKey::WheelDown => inputs.push(wheel(MOUSEEVENTF_WHEEL, -(WHEEL_DELTA as i32))),
+                MouseKey::WheelRight => inputs.push(wheel(MOUSEEVENTF_HWHEEL, WHEEL_DELTA as i32)),
+                MouseKey::WheelLeft => {
+                    inputs.push(wheel(MOUSEEVENTF_HWHEEL, -(WHEEL_DELTA as i32)))
+                }
+            }
+        }
+        KeyStrokeKind::Invisible(key) => {
+            if let Some(virt_key) = virt_key::from_invisible(key) {
+                inputs.push(input(virt_key as WORD, 0, 0));
+                inputs.push(input(virt_key as WORD, 0, KEYEVENTF_KEYUP));
+            }
+        }
+    }
+    push_modifiers(inputs, mods.iter_sided(), held, KEYEVENTF_KEYUP);
+}
+
+/// An unassigned virtual key, pressed before a tapped modifier gets released so that the modifier
+/// is not released by itself, which would make windows act on the tap (e.g. open the start menu).
+const MASK_VIRT_KEY: WORD = 0xe8;
+
+fn send_inputs(inputs: &mut [INPUT]) -> Result<(), WindowsBackendError> {
+    if inputs.is_empty() {
+        return Ok(());
+    }
+
+    WinapiError::from_return(unsafe {
+        SendInput(
+            inputs.len().try_into()?,
+            inputs.as_mut_ptr(),
+            const { mem::size_of::<INPUT>() as c_int },
+        )
+    })
+    .map(drop)
+    .map_err(<WinapiError as Into<WindowsBackendError>>::into)
+}
+
+pub fn inject_keys(keys: &KeySequence<'_>) -> Result<(), WindowsBackendError> {
+    let mut inputs = SmallVec::new();
+    let held = key_hook::held_modifiers();
+    keys.iter()
+        .flat_map(|key| key.strokes())
+        .for_each(|stroke| push_stroke(&mut inputs, stroke, held));
+
+    send_inputs(&mut inputs)
+}
+
+/// Release a modifier whose release was blocked, behind [MASK_VIRT_KEY] so that windows does not
+/// act on it being tapped.
+pub fn release_tapped(modifier: KeyModifier, side: KeySide) -> Result<(), WindowsBackendError> {
+    send_inputs(&mut [
+        input(MASK_VIRT_KEY, 0, 0),
+        input(MASK_VIRT_KEY, 0, KEYEVENTF_KEYUP),
+        input(
+            virt_key::modifier_virt_key(modifier, side) as WORD,
+            0,
+            KEYEVENTF_KEYUP,
+        ),
+    ])
+}
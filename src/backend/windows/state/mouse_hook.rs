@@ -0,0 +1,100 @@
+use {
+    super::{EVENT_SENDER, key_hook, key_inject},
+    crate::{
+        config::key::{Key, KeyKind, MouseKey},
+        state::{Event, KeyIntercept, KeyState},
+    },
+    std::{
+        ptr::null_mut,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+    winapi::{
+        ctypes::c_int,
+        shared::minwindef::{HIWORD, LPARAM, LRESULT, UINT, WPARAM},
+        um::winuser::{
+            CallNextHookEx, MSLLHOOKSTRUCT, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN,
+            WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN,
+            WM_RBUTTONUP, WM_XBUTTONDOWN, WM_XBUTTONUP, XBUTTON1, XBUTTON2,
+        },
+    },
+};
+
+/// Whether pointer motion is sent as [Event::Pointer], which is only set while a window is grabbed
+/// so that motion does not flood the event channel the rest of the time.
+pub static TRACK_POINTER: AtomicBool = AtomicBool::new(false);
+
+/// Returns none for mouse events that are neither buttons nor scrolling.
+fn translate_mouse(event_ident: UINT, mouse_diff: &MSLLHOOKSTRUCT) -> Option<(MouseKey, KeyState)> {
+    let data = HIWORD(mouse_diff.mouseData);
+
+    Some(match event_ident {
+        WM_LBUTTONDOWN => (MouseKey::Button(1), KeyState::Pressed),
+        WM_LBUTTONUP => (MouseKey::Button(1), KeyState::Released),
+        WM_MBUTTONDOWN => (MouseKey::Button(2), KeyState::Pressed),
+        WM_MBUTTONUP => (MouseKey::Button(2), KeyState::Released),
+        WM_RBUTTONDOWN => (MouseKey::Button(3), KeyState::Pressed),
+        WM_RBUTTONUP => (MouseKey::Button(3), KeyState::Released),
+        WM_XBUTTONDOWN | WM_XBUTTONUP => (
+            match data {
+                XBUTTON1 => MouseKey::Button(4),
+                XBUTTON2 => MouseKey::Button(5),
+                _ => return None,
+            },
+            match event_ident {
+                WM_XBUTTONDOWN => KeyState::Pressed,
+                _ => KeyState::Released,
+            },
+        ),
+        // the wheel delta is signed
+        WM_MOUSEWHEEL if data as i16 > 0 => (MouseKey::WheelUp, KeyState::Pressed),
+        WM_MOUSEWHEEL => (MouseKey::WheelDown, KeyState::Pressed),
+        WM_MOUSEHWHEEL if data as i16 > 0 => (MouseKey::WheelRight, KeyState::Pressed),
+        WM_MOUSEHWHEEL => (MouseKey::WheelLeft, KeyState::Pressed),
+        _ => return None,
+    })
+}
+
+pub unsafe extern "system" fn mouse_hook(
+    code: c_int,
+    event_ident: WPARAM,
+    mouse_diff: LPARAM,
+) -> LRESULT {
+    let call_next_hook = || unsafe { CallNextHookEx(null_mut(), code, event_ident, mouse_diff) };
+
+    if code < 0 {
+        return call_next_hook();
+    }
+    let Some(mouse_diff) = (unsafe { (mouse_diff as *const MSLLHOOKSTRUCT).as_ref() }) else {
+        return call_next_hook();
+    };
+    // buttons replayed by [key_inject::inject_keys] were already processed, and they do not move
+    // the pointer
+    if mouse_diff.dwExtraInfo == key_inject::INJECTED {
+        return call_next_hook();
+    }
+
+    if event_ident as UINT == WM_MOUSEMOVE {
+        if TRACK_POINTER.load(Ordering::Relaxed)
+            && let (Ok(x), Ok(y)) = (mouse_diff.pt.x.try_into(), mouse_diff.pt.y.try_into())
+            && let Some(sender) = EVENT_SENDER.read().as_ref()
+        {
+            drop(sender.send(Ok(Event::Pointer { x, y })));
+        }
+    } else if let Some((key, state)) = translate_mouse(event_ident as UINT, mouse_diff)
+        && let Some(sender) = EVENT_SENDER.read().as_ref()
+    {
+        let (tx, rx) = oneshot::channel();
+
+        drop(sender.send(Ok(Event::Key {
+            consume: tx,
+            key: Key::new(key_hook::modifiers(), KeyKind::Mouse(key)),
+            state,
+            scan_code: None,
+        })));
+        if matches!(rx.recv().unwrap_or_default(), KeyIntercept::Block) {
+            return 1;
+        }
+    }
+
+    call_next_hook()
+}
@@ -0,0 +1,128 @@
+//! Conversions between virtual key codes and [InvisibleKey]s.
+
+use {
+    crate::config::key::{InvisibleKey, KeyModifier, KeySide},
+    winapi::{
+        ctypes::c_int,
+        um::winuser::{
+            VK_ADD, VK_BACK, VK_CAPITAL, VK_CONTROL, VK_DECIMAL, VK_DELETE, VK_DIVIDE, VK_DOWN,
+            VK_END, VK_ESCAPE, VK_F1, VK_F24, VK_HOME, VK_INSERT, VK_LCONTROL, VK_LEFT, VK_LMENU,
+            VK_LSHIFT, VK_LWIN, VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK,
+            VK_MEDIA_STOP, VK_MENU, VK_MULTIPLY, VK_NEXT, VK_NUMLOCK, VK_NUMPAD0, VK_NUMPAD9,
+            VK_PAUSE, VK_PRIOR, VK_RCONTROL, VK_RETURN, VK_RIGHT, VK_RMENU, VK_RSHIFT, VK_RWIN,
+            VK_SCROLL, VK_SHIFT, VK_SNAPSHOT, VK_SPACE, VK_SUBTRACT, VK_TAB, VK_UP, VK_VOLUME_DOWN,
+            VK_VOLUME_MUTE, VK_VOLUME_UP,
+        },
+    },
+};
+
+/// Returns none for virtual keys that should be translated into text instead.
+pub const fn to_invisible(virt_key: c_int) -> Option<InvisibleKey> {
+    Some(match virt_key {
+        VK_F1..=VK_F24 => InvisibleKey::F((virt_key + 1 - VK_F1) as u8),
+        VK_NUMPAD0..=VK_NUMPAD9 => InvisibleKey::Numpad((virt_key - VK_NUMPAD0) as u8),
+        VK_LMENU => InvisibleKey::Modifier(KeyModifier::Alt, KeySide::Left),
+        VK_RMENU => InvisibleKey::Modifier(KeyModifier::Alt, KeySide::Right),
+        VK_LCONTROL => InvisibleKey::Modifier(KeyModifier::Control, KeySide::Left),
+        VK_RCONTROL => InvisibleKey::Modifier(KeyModifier::Control, KeySide::Right),
+        VK_LSHIFT => InvisibleKey::Modifier(KeyModifier::Shift, KeySide::Left),
+        VK_RSHIFT => InvisibleKey::Modifier(KeyModifier::Shift, KeySide::Right),
+        VK_LWIN => InvisibleKey::Modifier(KeyModifier::Super, KeySide::Left),
+        VK_RWIN => InvisibleKey::Modifier(KeyModifier::Super, KeySide::Right),
+        VK_PRIOR => InvisibleKey::PageUp,
+        VK_NEXT => InvisibleKey::PageDown,
+        VK_UP => InvisibleKey::Up,
+        VK_DOWN => InvisibleKey::Down,
+        VK_LEFT => InvisibleKey::Left,
+        VK_RIGHT => InvisibleKey::Right,
+        VK_HOME => InvisibleKey::Home,
+        VK_END => InvisibleKey::End,
+        VK_INSERT => InvisibleKey::Insert,
+        VK_DELETE => InvisibleKey::Delete,
+        VK_ESCAPE => InvisibleKey::Escape,
+        VK_TAB => InvisibleKey::Tab,
+        VK_RETURN => InvisibleKey::Return,
+        VK_BACK => InvisibleKey::Backspace,
+        VK_SPACE => InvisibleKey::Space,
+        VK_SNAPSHOT => InvisibleKey::PrintScreen,
+        VK_PAUSE => InvisibleKey::Pause,
+        VK_CAPITAL => InvisibleKey::CapsLock,
+        VK_NUMLOCK => InvisibleKey::NumLock,
+        VK_SCROLL => InvisibleKey::ScrollLock,
+        VK_ADD => InvisibleKey::NumpadAdd,
+        VK_SUBTRACT => InvisibleKey::NumpadSubtract,
+        VK_MULTIPLY => InvisibleKey::NumpadMultiply,
+        VK_DIVIDE => InvisibleKey::NumpadDivide,
+        VK_DECIMAL => InvisibleKey::NumpadDecimal,
+        VK_MEDIA_PLAY_PAUSE => InvisibleKey::MediaPlayPause,
+        VK_MEDIA_STOP => InvisibleKey::MediaStop,
+        VK_MEDIA_NEXT_TRACK => InvisibleKey::MediaNext,
+        VK_MEDIA_PREV_TRACK => InvisibleKey::MediaPrevious,
+        VK_VOLUME_UP => InvisibleKey::VolumeUp,
+        VK_VOLUME_DOWN => InvisibleKey::VolumeDown,
+        VK_VOLUME_MUTE => InvisibleKey::VolumeMute,
+        _ => return None,
+    })
+}
+
+/// Returns none for keys that have no virtual key code.
+///
+/// Brightness keys are handled by the firmware on Windows, so they are never reported.
+pub const fn from_invisible(key: InvisibleKey) -> Option<c_int> {
+    Some(match key {
+        InvisibleKey::F(n @ 1..=24) => VK_F1 + n as c_int - 1,
+        InvisibleKey::Numpad(n @ 0..=9) => VK_NUMPAD0 + n as c_int,
+        InvisibleKey::F(_) | InvisibleKey::Numpad(_) => return None,
+        InvisibleKey::Modifier(modifier, side) => modifier_virt_key(modifier, side),
+        InvisibleKey::PageUp => VK_PRIOR,
+        InvisibleKey::PageDown => VK_NEXT,
+        InvisibleKey::Up => VK_UP,
+        InvisibleKey::Down => VK_DOWN,
+        InvisibleKey::Left => VK_LEFT,
+        InvisibleKey::Right => VK_RIGHT,
+        InvisibleKey::Home => VK_HOME,
+        InvisibleKey::End => VK_END,
+        InvisibleKey::Insert => VK_INSERT,
+        InvisibleKey::Delete => VK_DELETE,
+        InvisibleKey::Escape => VK_ESCAPE,
+        InvisibleKey::Tab => VK_TAB,
+        InvisibleKey::Return => VK_RETURN,
+        InvisibleKey::Backspace => VK_BACK,
+        InvisibleKey::Space => VK_SPACE,
+        InvisibleKey::PrintScreen => VK_SNAPSHOT,
+        InvisibleKey::Pause => VK_PAUSE,
+        InvisibleKey::CapsLock => VK_CAPITAL,
+        InvisibleKey::NumLock => VK_NUMLOCK,
+        InvisibleKey::ScrollLock => VK_SCROLL,
+        InvisibleKey::NumpadAdd => VK_ADD,
+        InvisibleKey::NumpadSubtract => VK_SUBTRACT,
+        InvisibleKey::NumpadMultiply => VK_MULTIPLY,
+        InvisibleKey::NumpadDivide => VK_DIVIDE,
+        InvisibleKey::NumpadDecimal => VK_DECIMAL,
+        InvisibleKey::MediaPlayPause => VK_MEDIA_PLAY_PAUSE,
+        InvisibleKey::MediaStop => VK_MEDIA_STOP,
+        InvisibleKey::MediaNext => VK_MEDIA_NEXT_TRACK,
+        InvisibleKey::MediaPrevious => VK_MEDIA_PREV_TRACK,
+        InvisibleKey::VolumeUp => VK_VOLUME_UP,
+        InvisibleKey::VolumeDown => VK_VOLUME_DOWN,
+        InvisibleKey::VolumeMute => VK_VOLUME_MUTE,
+        InvisibleKey::BrightnessUp | InvisibleKey::BrightnessDown => return None,
+    })
+}
+
+pub const fn modifier_virt_key(modifier: KeyModifier, side: KeySide) -> c_int {
+    match (modifier, side) {
+        (KeyModifier::Alt, KeySide::Any) => VK_MENU,
+        (KeyModifier::Alt, KeySide::Left) => VK_LMENU,
+        (KeyModifier::Alt, KeySide::Right) => VK_RMENU,
+        (KeyModifier::Control, KeySide::Any) => VK_CONTROL,
+        (KeyModifier::Control, KeySide::Left) => VK_LCONTROL,
+        (KeyModifier::Control, KeySide::Right) => VK_RCONTROL,
+        (KeyModifier::Shift, KeySide::Any) => VK_SHIFT,
+        (KeyModifier::Shift, KeySide::Left) => VK_LSHIFT,
+        (KeyModifier::Shift, KeySide::Right) => VK_RSHIFT,
+        // there is no virtual key for either of the super keys
+        (KeyModifier::Super, KeySide::Any | KeySide::Left) => VK_LWIN,
+        (KeyModifier::Super, KeySide::Right) => VK_RWIN,
+    }
+}
@@ -51,6 +51,11 @@ impl TryFrom<HWND> for WindowsWindow {
         }
     }
 }
+impl PartialEq for WindowsWindow {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.as_ptr() == rhs.as_ptr()
+    }
+}
 impl From<NonNull<HWND__>> for WindowsWindow {
     fn from(handle: NonNull<HWND__>) -> Self {
         Self(AtomicPtr::new(handle.as_ptr()))
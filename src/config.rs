@@ -1,28 +1,108 @@
+pub mod command;
+pub mod expand;
 pub mod file_parser;
 pub mod key;
+pub mod mode;
 pub mod opts;
+pub mod suggest;
+pub mod tokenizer;
 
 use {
-    crate::{NAME, VERSION, const_string::ConstString, path_cache::PathCache},
+    crate::{
+        NAME, VERSION,
+        const_string::ConstString,
+        layout::Placement,
+        path_cache::{PathCache, PathOrigin},
+        state::KeyIntercept,
+    },
+    command::{Command, RestartPolicy, Service},
     either::Either,
-    enum_map::EnumMap,
-    key::{KeyAction, KeySequence, Parser, ParserError},
-    opts::{Argv, Flag},
+    expand::ExpandError,
+    file_parser::FileParser,
+    key::{InvisibleKey, KeyAction, KeySequence, Parser, ParserError},
+    mode::{KeyBinding, KeyConflict, KeyTrigger, ModeId, Modes},
+    opts::{Argv, ArgvError, Flag},
     phf::phf_map,
     smallvec::SmallVec,
     std::{
+        borrow::Cow,
+        cell::RefCell,
         cmp::{Ordering, PartialOrd},
+        collections::HashMap,
+        convert::Infallible,
+        env,
         ffi::{CStr, c_char, c_int},
         fmt::{self, Display, Formatter},
-        fs::File,
+        fs::{self, File},
         io::{self, Write, stderr},
-        num::TryFromIntError,
-        path::Path,
+        mem,
+        num::{ParseIntError, TryFromIntError},
+        ops::ControlFlow,
+        path::{Path, PathBuf},
         str::Utf8Error,
+        time::Duration,
     },
     strum::VariantArray,
+    suggest::Suggestions,
+    tokenizer::{Tokenizer, TokenizerError},
 };
 
+/// The [KeyAction]s without an argument by name, which are suggested for unknown actions.
+const KEY_ACTIONS: [(&str, KeyAction); 7] = [
+    ("kill", KeyAction::Kill),
+    ("leave-mode", KeyAction::LeaveMode),
+    ("move-window", KeyAction::MoveWindow),
+    ("quit", KeyAction::Quit),
+    ("reload", KeyAction::Reload),
+    ("resize-window", KeyAction::ResizeWindow),
+    ("toggle-floating", KeyAction::ToggleFloating),
+];
+/// Values of `--key-intercept`.
+const KEY_INTERCEPTS: [(&str, KeyIntercept); 2] = [
+    ("consume", KeyIntercept::Block),
+    ("pass", KeyIntercept::Allow),
+];
+/// Values of `--key-trigger`.
+const KEY_TRIGGERS: [(&str, KeyTrigger); 3] = [
+    ("press", KeyTrigger::Press),
+    ("release", KeyTrigger::Release),
+    ("repeat", KeyTrigger::Repeat),
+];
+/// Values of `--key-unmatched`.
+const KEY_UNMATCHED: [(&str, KeyIntercept); 2] = [
+    ("allow", KeyIntercept::Allow),
+    ("block", KeyIntercept::Block),
+];
+/// Values of `--key-conflicts`.
+const CONFLICT_POLICIES: [(&str, ConflictPolicy); 2] = [
+    ("warn", ConflictPolicy::Warn),
+    ("fatal", ConflictPolicy::Fatal),
+];
+/// Values of `--restart`.
+const RESTART_POLICIES: [(&str, RestartPolicy); 3] = [
+    ("always", RestartPolicy::Always),
+    ("on-failure", RestartPolicy::OnFailure),
+    ("never", RestartPolicy::Never),
+];
+/// Values of `--log-level`.
+const LOG_LEVELS: [&str; 3] = ["none", "quiet", "verbose"];
+/// Values of `--default`.
+const DEFAULTS: [&str; 1] = ["config"];
+/// What `--if` can check.
+const CONDITIONS: [&str; 3] = ["host", "os", "profile"];
+
+/// The value named `name` in `table`.
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| *value)
+}
+/// The names in `table`, to suggest for unknown values.
+fn names<'a, T>(table: &'a [(&'static str, T)]) -> impl Iterator<Item = &'static str> + 'a {
+    table.iter().map(|(name, _)| *name)
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[repr(u8)]
 /// Determines how verbose log messages should be.
@@ -57,66 +137,451 @@ impl LogLevel {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Determines what happens when key bindings conflict with each other.
+enum ConflictPolicy {
+    /// Report conflicts and keep going.
+    #[default]
+    Warn,
+    /// Report conflicts and refuse to start.
+    Fatal,
+}
+
+#[derive(Debug, PartialEq)]
+/// Returned by [Config::build_key_tries] when conflicts are fatal, with how many were found.
+pub struct KeyConflictsError(usize);
+impl Display for KeyConflictsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "found {} key binding conflict(s), which are fatal because of `--key-conflicts=fatal`",
+            self.0
+        )
+    }
+}
+
 #[derive(Debug, Default)]
 /// Errors that occur during configuration parsing are reported to stderr, as they could be
 /// important and [Self::log_file] may be incomplete.
-pub struct Config<'a> {
-    commands: SmallVec<[&'a str; 8]>,
-    pub config_file: Option<&'a Path>,
+pub struct Config {
+    /// The arguments that [Self::apply_argv] applied, which [Self::reload] applies again.
+    args: SmallVec<[String; 8]>,
+    commands: SmallVec<[Command; 8]>,
+    pub config_file: Option<PathBuf>,
+    /// The config files that are being applied, with the innermost last, which `--include`
+    /// resolves relative paths against.
+    files: SmallVec<[PathBuf; 2]>,
+    key_conflicts: ConflictPolicy,
     log_level: LogLevel,
     log_file: Option<File>,
-    pub key_bindings: EnumMap<KeyAction, SmallVec<[KeySequence<'a>; 2]>>,
+    pub modes: Modes,
+    /// The profile that `--if profile=NAME` checks for.
+    profile: Option<String>,
+    /// How long to wait for the next key of a key sequence before abandoning it.
+    pub key_timeout: Option<Duration>,
+    /// Executed when a key sequence gets abandoned because of [Self::key_timeout].
+    pub timeout_action: Option<KeyAction>,
+    /// How many pixels [Placement]s move and resize floating windows by.
+    move_step: Option<u16>,
+    pub services: SmallVec<[Service; 4]>,
+    /// Whether to reload when one of [Self::watched] changes.
+    pub watch: bool,
+    /// The config files and drop-in directories that [Self::apply_config_files] went through,
+    /// whether they exist or not.
+    pub watched: Vec<PathBuf>,
 
     key_action: Option<KeyAction>,
+    /// Whether new key bindings let their last key reach the focused window, which is
+    /// [KeyIntercept::Block] if unset.
+    key_intercept: Option<KeyIntercept>,
+    key_trigger: KeyTrigger,
+    /// The mode that new key bindings belong to.
+    mode: ModeId,
+    restart_policy: RestartPolicy,
+    /// Values defined by `--define`, which `$NAME` expands to before environment variables.
+    variables: HashMap<String, String>,
+    /// Whether the condition of each `--if` that has not been ended yet holds, along with the
+    /// [Argv::args_read] at the `--if` so errors can point to it, with the innermost last.
+    conditions: SmallVec<[(bool, usize); 2]>,
 }
-impl<'a> Config<'a> {
-    pub fn max_key_binding_len(&self) -> usize {
-        self
-            .key_bindings
-            .values()
-            .flat_map(|sequences| sequences.iter())
-            .map(|sequence| sequence.len())
-            .max()
-            .unwrap_or(0)
+impl Config {
+    pub const DEFAULT_MOVE_STEP: u16 = 20;
+
+    pub fn move_step(&self) -> u16 {
+        self.move_step.unwrap_or(Self::DEFAULT_MOVE_STEP)
+    }
+
+    /// Prepare the key bindings for matching, which should happen after all arguments are applied.
+    ///
+    /// Key bindings that conflict with each other are reported, and are an error if
+    /// [Self::key_conflicts] is [ConflictPolicy::Fatal].
+    pub fn build_key_tries(&mut self) -> Result<(), KeyConflictsError> {
+        let mut modes = mem::take(&mut self.modes);
+        let mut conflicts = 0;
+        modes.build_key_tries(|mode, conflict| {
+            conflicts += 1;
+            self.error(|f| match conflict {
+                KeyConflict::Duplicate { ignored, .. } => writeln!(
+                    f,
+                    "key sequence `{}` is bound more than once in mode `{}`, only the first binding is used",
+                    ignored.sequence, mode.name
+                ),
+                KeyConflict::Prefix {
+                    prefix,
+                    unreachable,
+                } => writeln!(
+                    f,
+                    "key sequence `{}` can never be matched in mode `{}`, because it starts with `{}` which is bound as well",
+                    unreachable.sequence, mode.name, prefix.sequence
+                ),
+                KeyConflict::Overlap { used, shadowed } => writeln!(
+                    f,
+                    "key sequence `{}` overlaps with `{}` in mode `{}`, which is matched instead whenever both could be",
+                    shadowed.sequence, used.sequence, mode.name
+                ),
+            })
+        });
+        self.modes = modes;
+
+        match self.key_conflicts {
+            ConflictPolicy::Fatal if conflicts != 0 => Err(KeyConflictsError(conflicts)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply [Self::args] and the config files again to a new config, which is only returned if
+    /// there were no errors.
+    ///
+    /// Errors are reported through [Self::error].
+    pub fn reload(&mut self) -> Option<Config> {
+        let args = self.args.clone();
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        if let Err(err) = config.apply_args(
+            &paths,
+            args.iter().map(|arg| Ok::<_, Infallible>(arg.as_str())),
+        ) {
+            self.error(|f| writeln!(f, "failed to reload configuration: {}", err));
+            return None;
+        }
+        if let Err(errors) = config.apply_config_files(&paths) {
+            self.error(|f| writeln!(f, "failed to reload configuration:"));
+            errors
+                .iter()
+                .for_each(|err| self.error(|f| writeln!(f, "{}", err)));
+            return None;
+        }
+        if let Err(err) = config.build_key_tries() {
+            self.error(|f| writeln!(f, "failed to reload configuration: {}", err));
+            return None;
+        }
+
+        config.args = args;
+        Some(config)
     }
 
     /// Remove state
     pub fn clean_state(&mut self) {
         self.key_action = None;
+        self.key_intercept = None;
+        self.key_trigger = KeyTrigger::default();
+        self.mode = ModeId::DEFAULT;
+        self.restart_policy = RestartPolicy::default();
     }
 
-    pub fn apply_args<I: IntoIterator<Item = Result<&'a S, E>>, S: AsRef<str> + ?Sized + 'a, E>(
+    /// Apply `args`, stopping at the first error.
+    pub fn apply_args<'a, I, S, E>(
         &mut self,
         paths: &'a PathCache,
         args: I,
     ) -> Result<(), ApplyError<'a, E>>
     where
         E: Display,
+        I: IntoIterator<Item = Result<&'a S, E>>,
+        S: AsRef<str> + ?Sized + 'a,
+    {
+        self.apply_each(paths, args, true, ControlFlow::Break)
+    }
+
+    /// Apply the lines of the config file at `path`, which contains `contents`.
+    ///
+    /// Unlike [Self::apply_args], errors do not stop the rest of the file from being applied,
+    /// except for [ApplyError::Exit], and a command cannot be given after `--`.
+    pub fn apply_file<'a>(
+        &mut self,
+        paths: &'a PathCache,
+        path: &'a Path,
+        contents: &'a str,
+    ) -> Result<(), SmallVec<[FileError<'a>; 1]>> {
+        // the line of each argument, which [ApplyError::UnclosedIf] refers to by its position
+        let lines = RefCell::new(SmallVec::<[(usize, &'a str); 16]>::new());
+        let errors = RefCell::new(SmallVec::new());
+        let error = |line: (usize, &'a str), error| {
+            let (line, text) = line;
+            errors.borrow_mut().push(FileError {
+                path,
+                line,
+                text,
+                error,
+            });
+        };
+
+        self.files.push(path.to_path_buf());
+        let _ = self.apply_each(
+            paths,
+            FileParser::new(contents)
+                .flat_map(|(number, text)| {
+                    Tokenizer::new(text).map(move |token| ((number, text), token))
+                })
+                .filter_map(|(number, token)| match token {
+                    Ok(token) => {
+                        lines.borrow_mut().push(number);
+                        Some(match token {
+                            Cow::Borrowed(token) => token,
+                            Cow::Owned(token) => paths.keep_str(token),
+                        })
+                    }
+                    Err(err) => {
+                        error(number, ApplyError::Tokenizer(err));
+                        None
+                    }
+                })
+                .map(Ok::<_, Infallible>),
+            false,
+            |err| match err {
+                ApplyError::Exit => ControlFlow::Break(err),
+                ApplyError::Included(included) => {
+                    errors.borrow_mut().extend(included);
+                    ControlFlow::Continue(())
+                }
+                err => {
+                    let lines = lines.borrow();
+                    let line = match err {
+                        ApplyError::UnclosedIf(position) => lines[position - 1],
+                        _ => lines.last().copied().unwrap_or((0, "")),
+                    };
+                    error(line, err);
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        self.files.pop();
+
+        let errors = errors.into_inner();
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Read and apply the config file at `path`, see [Self::apply_file].
+    ///
+    /// Errors in the file are returned as [ApplyError::Included].
+    pub fn include<'a, E>(
+        &mut self,
+        paths: &'a PathCache,
+        path: PathBuf,
+    ) -> Result<(), ApplyError<'a, E>>
+    where
+        E: Display,
+    {
+        if self.files.iter().any(|file| same_file(file, &path)) {
+            return Err(ApplyError::IncludeCycle(paths.keep(path)));
+        }
+
+        let (path, contents) = paths.read(path);
+        let contents = contents.map_err(|err| ApplyError::FileOpen(path, err))?;
+
+        self.apply_file(paths, path, contents)
+            .map_err(|errors| ApplyError::Included(errors.into_vec()))
+    }
+
+    /// Apply the config file from [PathCache::get_config], then the files in its drop-in
+    /// directory, see [drop_ins].
+    ///
+    /// Errors inside of the files do not stop the rest from being applied, and files that cannot
+    /// be read are errors unless it is the default config file.
+    pub fn apply_config_files<'a>(
+        &mut self,
+        paths: &'a PathCache,
+    ) -> Result<(), SmallVec<[ApplyError<'a, Infallible>; 1]>> {
+        let mut errors = SmallVec::new();
+
+        if let Some((path, origin)) = paths
+            .get_config(self)
+            .map(|(path, origin)| (path.to_path_buf(), origin))
+        {
+            let dir = paths.keep(drop_in_dir(&path));
+
+            match self.include(paths, path) {
+                Ok(()) => {}
+                Err(ApplyError::FileOpen(..)) if matches!(origin, PathOrigin::Default) => {}
+                Err(err) => errors.push(err),
+            }
+            match drop_ins(dir) {
+                Ok(drop_ins) => errors.extend(
+                    drop_ins
+                        .into_iter()
+                        .filter_map(|drop_in| self.include(paths, drop_in).err()),
+                ),
+                Err(err) => errors.push(ApplyError::FileOpen(dir, err)),
+            }
+        }
+        self.watched = paths.kept();
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Apply `args`, passing each error to `error` which decides whether to stop.
+    ///
+    /// `--if` blocks have to be ended within `args`, so that they cannot span multiple files.
+    fn apply_each<'a, I, S, E, F>(
+        &mut self,
+        paths: &'a PathCache,
+        args: I,
+        command: bool,
+        mut error: F,
+    ) -> Result<(), ApplyError<'a, E>>
+    where
+        E: Display,
+        I: IntoIterator<Item = Result<&'a S, E>>,
+        S: AsRef<str> + ?Sized + 'a,
+        F: FnMut(ApplyError<'a, E>) -> ControlFlow<ApplyError<'a, E>>,
+    {
+        let outer = mem::take(&mut self.conditions);
+        let result = self.apply_flags(paths, args, command, &mut error);
+
+        match mem::replace(&mut self.conditions, outer).first() {
+            Some(&(_, position)) if result.is_ok() => {
+                match error(ApplyError::UnclosedIf(position)) {
+                    ControlFlow::Break(err) => Err(err),
+                    ControlFlow::Continue(()) => Ok(()),
+                }
+            }
+            _ => result,
+        }
+    }
+
+    /// Whether flags are currently skipped because of a condition that does not hold.
+    fn skipping(&self) -> bool {
+        self.conditions.iter().any(|&(holds, _)| !holds)
+    }
+
+    /// Apply the flags in `args` and, if `command` is set, the command after `--`, skipping flags
+    /// inside of `--if` blocks whose condition does not hold.
+    fn apply_flags<'a, I, S, E, F>(
+        &mut self,
+        paths: &'a PathCache,
+        args: I,
+        command: bool,
+        error: &mut F,
+    ) -> Result<(), ApplyError<'a, E>>
+    where
+        E: Display,
+        I: IntoIterator<Item = Result<&'a S, E>>,
+        S: AsRef<str> + ?Sized + 'a,
+        F: FnMut(ApplyError<'a, E>) -> ControlFlow<ApplyError<'a, E>>,
     {
         let mut parser = Argv::from(args.into_iter().map(|arg| arg.map(|arg| arg.as_ref())));
         while let Some(flag) = parser.next() {
-            let flag = flag.map_err(ApplyError::ArgSource)?;
-            let Some(cli_flag) = (match &flag {
-                Flag::Short(short) => CliFlags::SHORT.get(short),
-                Flag::Long(long) => CliFlags::LONG.get(long.as_ref()),
-            }) else {
-                return Err(ApplyError::UnknownFlag(flag));
+            let result = match flag {
+                Ok(flag) => match match &flag {
+                    Flag::Short(short) => CliFlags::SHORT.get(short),
+                    Flag::Long(long) => CliFlags::LONG.get(long.as_ref()),
+                } {
+                    // skipped flags still take their value, so that it is not read as flags
+                    Some(cli_flag)
+                        if self.skipping() && !matches!(cli_flag, CliFlags::If | CliFlags::End) =>
+                    {
+                        match cli_flag.takes_value() {
+                            true => parser
+                                .value()
+                                .transpose()
+                                .map(drop)
+                                .map_err(ApplyError::ArgSource),
+                            false => Ok(()),
+                        }
+                    }
+                    Some(cli_flag) => cli_flag.apply(self, paths, flag, &mut parser),
+                    None => Err(ApplyError::UnknownFlag(flag)),
+                },
+                Err(ArgvError::Positional(arg)) => Err(ApplyError::UnexpectedArgument(arg)),
+                Err(ArgvError::Source(err)) => Err(ApplyError::ArgSource(err)),
             };
 
-            cli_flag.apply(self, paths, flag, &mut parser)?;
+            if let Err(err) = result {
+                // the rest could be misread as short flags, such as `-foo` for `--foo` or `-kq`
+                // without a key action
+                parser.skip_arg();
+
+                if let ControlFlow::Break(err) = error(err) {
+                    return Err(err);
+                }
+            }
+        }
+
+        match parser.remainder() {
+            Some(_) if !command => {
+                if let ControlFlow::Break(err) = error(ApplyError::UnexpectedSeparator) {
+                    return Err(err);
+                }
+            }
+            Some(rest) if !self.skipping() => match rest.collect::<Result<SmallVec<[_; 4]>, E>>() {
+                Ok(argv) if !argv.is_empty() => self.commands.insert(
+                    0,
+                    Command::Argv(argv.into_iter().map(str::to_owned).collect()),
+                ),
+                Ok(_) => {}
+                Err(err) => {
+                    if let ControlFlow::Break(err) = error(ApplyError::ArgSource(err)) {
+                        return Err(err);
+                    }
+                }
+            },
+            _ => {}
         }
+
         Ok(())
     }
 
+    fn parse_key_action(&mut self, action: &str) -> Option<KeyAction> {
+        if let Some(placement) = Placement::from_name(action) {
+            return Some(KeyAction::Place(placement));
+        }
+
+        match action.strip_prefix("enter-mode:") {
+            Some(name) if !name.is_empty() => {
+                Some(KeyAction::EnterMode(self.modes.get_or_insert(name)))
+            }
+            _ => lookup(&KEY_ACTIONS, action),
+        }
+    }
+
+    /// Spawn all of [Self::commands], which get removed so they can only run once.
+    pub fn autostart(&mut self) {
+        mem::take(&mut self.commands)
+            .into_iter()
+            .for_each(|command| match command.spawn() {
+                Ok(_) => self.log(|f| writeln!(f, "started command `{}`", command)),
+                Err(err) => {
+                    self.error(|f| writeln!(f, "failed to start command `{}`: {}", command, err))
+                }
+            });
+    }
+
     /// # SAFETY
     ///
     /// `argc` must be accurate and `argv` must point to owned memory addresses
-    pub unsafe fn apply_argv(
+    pub unsafe fn apply_argv<'a>(
         &mut self,
         paths: &'a PathCache,
         argc: c_int,
         argv: *const *const c_char,
-    ) -> Result<(), Either<ApplyArgvError, ApplyError<ApplyArgvError>>> {
+    ) -> Result<(), Either<ApplyArgvError, ApplyError<'a, ApplyArgvError>>> {
         if argc < 0 {
             Err(Either::Left(ApplyArgvError::NegativeArgc))
         } else if argv.is_null() {
@@ -141,7 +606,17 @@ impl<'a> Config<'a> {
                         .map_err(|err| ApplyArgvError::Utf8(i, err))
                 });
 
-            self.apply_args(paths, argv).map_err(Either::Right)
+            // the first argument is the name of the program
+            let mut args = SmallVec::<[_; 8]>::new();
+            let result = self
+                .apply_args(
+                    paths,
+                    argv.skip(1).inspect(|arg| args.extend(arg.iter().copied())),
+                )
+                .map_err(Either::Right);
+            self.args = args.into_iter().map(str::to_owned).collect();
+
+            result
         }
     }
 
@@ -196,14 +671,34 @@ where
 {
     ArgSource(E),
     Exit,
-    FileOpen(&'a str, io::Error),
+    Expand(ExpandError<'a>),
+    FileOpen(&'a Path, io::Error),
+    /// Errors in an included config file.
+    Included(Vec<FileError<'a>>),
+    IncludeCycle(&'a Path),
+    InvalidDefine(&'a str),
+    /// An `--if` without an `--end`, along with the [Argv::args_read] at the `--if`.
+    UnclosedIf(usize),
+    UnknownCondition(&'a str),
+    UnmatchedEnd,
     KeyParser(key::ParserError<'a>),
     MissingValue(Flag<'a>),
     NoConfigPath,
+    ParseInt(&'a str, ParseIntError),
+    Tokenizer(TokenizerError),
+    /// An argument that is neither a flag nor the value of one.
+    UnexpectedArgument(&'a str),
+    /// A `--` outside of the command line.
+    UnexpectedSeparator,
+    UnknownConflictPolicy(&'a str),
     UnknownDefault(&'a str),
     UnknownLogLevel(&'a str),
     UnknownFlag(Flag<'a>),
     UnknownKeyAction(&'a str),
+    UnknownKeyIntercept(&'a str),
+    UnknownKeyTrigger(&'a str),
+    UnknownKeyUnmatched(&'a str),
+    UnknownRestartPolicy(&'a str),
     UnsetKeyAction,
 }
 impl<E> Display for ApplyError<'_, E>
@@ -214,14 +709,110 @@ where
         match self {
             Self::ArgSource(err) => write!(f, "failed to source arguments: {}", err),
             Self::Exit => Ok(()),
-            Self::FileOpen(path, error) => write!(f, "failed to open file `{}`: {}", path, error),
+            Self::Expand(err) => write!(f, "failed to expand value: {}", err),
+            Self::FileOpen(path, error) => {
+                write!(f, "failed to open file `{}`: {}", path.display(), error)
+            }
+            Self::Included(errors) => errors.iter().enumerate().try_for_each(|(i, error)| {
+                if i > 0 {
+                    writeln!(f)?;
+                }
+                write!(f, "{}", error)
+            }),
+            Self::IncludeCycle(path) => {
+                write!(f, "`{}` includes itself", path.display())
+            }
+            Self::InvalidDefine(define) => write!(
+                f,
+                "invalid definition `{}`, expected `NAME=VALUE` where NAME only contains letters, digits and underscores",
+                define
+            ),
+            Self::UnclosedIf(_) => write!(f, "`--if` is missing a matching `--end`"),
+            Self::UnknownCondition(condition) => write!(
+                f,
+                "unknown condition: {}{}",
+                condition,
+                Suggestions::new(condition, CONDITIONS)
+            ),
+            Self::UnmatchedEnd => write!(f, "`--end` is missing a matching `--if`"),
             Self::KeyParser(err) => write!(f, "failed to parse keys: {}", err),
             Self::MissingValue(flag) => write!(f, "flag `{}` is missing an argument", flag),
             Self::NoConfigPath => write!(f, "failed to get default config path"),
-            Self::UnknownDefault(def) => write!(f, "unknown configuration option: {}", def),
-            Self::UnknownLogLevel(level) => write!(f, "unknown log level: {}", level),
+            Self::ParseInt(value, err) => write!(f, "failed to parse `{}`: {}", value, err),
+            Self::Tokenizer(err) => write!(f, "failed to split line into arguments: {}", err),
+            Self::UnexpectedArgument(arg) => write!(f, "unexpected argument `{}`", arg),
+            Self::UnexpectedSeparator => {
+                write!(f, "`--` is only allowed on the command line")
+            }
+            Self::UnknownConflictPolicy(policy) => write!(
+                f,
+                "unknown key conflict policy: {}{}",
+                policy,
+                Suggestions::new(policy, names(&CONFLICT_POLICIES))
+            ),
+            Self::UnknownDefault(def) => write!(
+                f,
+                "unknown configuration option: {}{}",
+                def,
+                Suggestions::new(def, DEFAULTS)
+            ),
+            Self::UnknownLogLevel(level) => write!(
+                f,
+                "unknown log level: {}{}",
+                level,
+                Suggestions::new(level, LOG_LEVELS)
+            ),
+            Self::UnknownFlag(flag @ Flag::Long(_)) => write!(
+                f,
+                "unknown flag `{}`{}",
+                flag,
+                Suggestions::new(
+                    &flag.to_string(),
+                    CliFlags::LONG.keys().map(|long| format!("--{}", long))
+                )
+            ),
             Self::UnknownFlag(flag) => write!(f, "unknown flag `{}`", flag),
-            Self::UnknownKeyAction(action) => write!(f, "unknown key action: {}", action),
+            Self::UnknownKeyAction(action) => write!(
+                f,
+                "unknown key action: {}{}",
+                action,
+                Suggestions::new(
+                    action,
+                    names(&KEY_ACTIONS)
+                        .map(str::to_owned)
+                        .chain(Placement::names())
+                        // keep the name of the mode if only `enter-mode` is misspelled
+                        .chain(
+                            action
+                                .split_once(':')
+                                .map(|(_, mode)| format!("enter-mode:{}", mode))
+                        )
+                )
+            ),
+            Self::UnknownKeyIntercept(intercept) => write!(
+                f,
+                "unknown key intercept: {}{}",
+                intercept,
+                Suggestions::new(intercept, names(&KEY_INTERCEPTS))
+            ),
+            Self::UnknownKeyTrigger(trigger) => write!(
+                f,
+                "unknown key trigger: {}{}",
+                trigger,
+                Suggestions::new(trigger, names(&KEY_TRIGGERS))
+            ),
+            Self::UnknownKeyUnmatched(unmatched) => write!(
+                f,
+                "unknown value for unmatched keys: {}{}",
+                unmatched,
+                Suggestions::new(unmatched, names(&KEY_UNMATCHED))
+            ),
+            Self::UnknownRestartPolicy(policy) => write!(
+                f,
+                "unknown restart policy: {}{}",
+                policy,
+                Suggestions::new(policy, names(&RESTART_POLICIES))
+            ),
             Self::UnsetKeyAction => write!(f, "`key-action` is not set"),
         }
     }
@@ -235,6 +826,28 @@ where
     }
 }
 
+#[derive(Debug)]
+/// An error in a config file, which is rendered as `path:line: error` followed by the line.
+pub struct FileError<'a> {
+    pub path: &'a Path,
+    pub line: usize,
+    /// The line that the error is on.
+    pub text: &'a str,
+    pub error: ApplyError<'a, Infallible>,
+}
+impl Display for FileError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}\n    {}",
+            self.path.display(),
+            self.line,
+            self.error,
+            self.text
+        )
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, VariantArray)]
 enum CliFlags {
     Help,
@@ -242,10 +855,29 @@ enum CliFlags {
     LogLevel,
     LogOutput,
 
+    Autostart,
+    Service,
+    Restart,
+
     KeyAction,
     KeySequence,
+    KeyIntercept,
+    KeyTrigger,
+    KeyMode,
+    KeyUnmatched,
+    KeyTimeout,
+    TimeoutAction,
+    KeyConflicts,
+
+    MoveStep,
 
     ConfigFile,
+    Include,
+    Watch,
+    Define,
+    Profile,
+    If,
+    End,
     PrintDefault,
 }
 impl CliFlags {
@@ -255,10 +887,29 @@ impl CliFlags {
         'l' => CliFlags::LogLevel,
         'o' => CliFlags::LogOutput,
 
+        'a' => CliFlags::Autostart,
+        's' => CliFlags::Service,
+        'R' => CliFlags::Restart,
+
         'K' => CliFlags::KeyAction,
         'k' => CliFlags::KeySequence,
+        'I' => CliFlags::KeyIntercept,
+        'r' => CliFlags::KeyTrigger,
+        'm' => CliFlags::KeyMode,
+        'u' => CliFlags::KeyUnmatched,
+        't' => CliFlags::KeyTimeout,
+        'T' => CliFlags::TimeoutAction,
+        'C' => CliFlags::KeyConflicts,
+
+        'S' => CliFlags::MoveStep,
 
         'c' => CliFlags::ConfigFile,
+        'i' => CliFlags::Include,
+        'w' => CliFlags::Watch,
+        'D' => CliFlags::Define,
+        'P' => CliFlags::Profile,
+        'F' => CliFlags::If,
+        'E' => CliFlags::End,
         'd' => CliFlags::PrintDefault,
     };
     const LONG: phf::Map<&str, CliFlags> = phf_map! {
@@ -267,10 +918,29 @@ impl CliFlags {
         "log-level" => CliFlags::LogLevel,
         "log-output" => CliFlags::LogOutput,
 
+        "autostart" => CliFlags::Autostart,
+        "service" => CliFlags::Service,
+        "restart" => CliFlags::Restart,
+
         "key-action" => CliFlags::KeyAction,
         "key-sequence" => CliFlags::KeySequence,
+        "key-intercept" => CliFlags::KeyIntercept,
+        "key-trigger" => CliFlags::KeyTrigger,
+        "key-mode" => CliFlags::KeyMode,
+        "key-unmatched" => CliFlags::KeyUnmatched,
+        "key-timeout" => CliFlags::KeyTimeout,
+        "timeout-action" => CliFlags::TimeoutAction,
+        "key-conflicts" => CliFlags::KeyConflicts,
+
+        "move-step" => CliFlags::MoveStep,
 
         "config" => CliFlags::ConfigFile,
+        "include" => CliFlags::Include,
+        "watch" => CliFlags::Watch,
+        "define" => CliFlags::Define,
+        "profile" => CliFlags::Profile,
+        "if" => CliFlags::If,
+        "end" => CliFlags::End,
         "default" => CliFlags::PrintDefault,
     };
 
@@ -281,10 +951,29 @@ impl CliFlags {
             Self::LogLevel => 'l',
             Self::LogOutput => 'o',
 
+            Self::Autostart => 'a',
+            Self::Service => 's',
+            Self::Restart => 'R',
+
             Self::KeyAction => 'K',
             Self::KeySequence => 'k',
+            Self::KeyIntercept => 'I',
+            Self::KeyTrigger => 'r',
+            Self::KeyMode => 'm',
+            Self::KeyUnmatched => 'u',
+            Self::KeyTimeout => 't',
+            Self::TimeoutAction => 'T',
+            Self::KeyConflicts => 'C',
+
+            Self::MoveStep => 'S',
 
             Self::ConfigFile => 'c',
+            Self::Include => 'i',
+            Self::Watch => 'w',
+            Self::Define => 'D',
+            Self::Profile => 'P',
+            Self::If => 'F',
+            Self::End => 'E',
             Self::PrintDefault => 'd',
         }
     }
@@ -295,10 +984,29 @@ impl CliFlags {
             Self::LogLevel => "log-level",
             Self::LogOutput => "log-output",
 
+            Self::Autostart => "autostart",
+            Self::Service => "service",
+            Self::Restart => "restart",
+
             Self::KeyAction => "key-action",
             Self::KeySequence => "key-sequence",
+            Self::KeyIntercept => "key-intercept",
+            Self::KeyTrigger => "key-trigger",
+            Self::KeyMode => "key-mode",
+            Self::KeyUnmatched => "key-unmatched",
+            Self::KeyTimeout => "key-timeout",
+            Self::TimeoutAction => "timeout-action",
+            Self::KeyConflicts => "key-conflicts",
+
+            Self::MoveStep => "move-step",
 
             Self::ConfigFile => "config",
+            Self::Include => "include",
+            Self::Watch => "watch",
+            Self::Define => "define",
+            Self::Profile => "profile",
+            Self::If => "if",
+            Self::End => "end",
             Self::PrintDefault => "default",
         }
     }
@@ -351,6 +1059,11 @@ impl CliFlags {
         ConstString::new_filled(b' ')
     }
 
+    /// Whether [Self::apply] takes a value after the flag.
+    const fn takes_value(&self) -> bool {
+        !matches!(self, Self::Help | Self::Version | Self::Watch | Self::End)
+    }
+
     const fn help(&self) -> &'static [&'static str] {
         match self {
             Self::Help => &["Print this message and exit."],
@@ -366,44 +1079,156 @@ impl CliFlags {
                 "Set which file to print logs.",
                 "If unset, defaults to stderr.",
             ],
+            Self::Autostart => &[
+                "Run a command once after the window manager has started.",
+                "Commands are run by `sh -c` (`cmd /C` on Windows) in the order they were given.",
+                "Arguments after `--` on the command line are run without a shell before any other command.",
+            ],
+            Self::Service => &[
+                "Run a command for as long as the window manager runs, restarting it when it exits.",
+                "The command is split into arguments the same way as a line of the config file, and is run without a shell",
+                "so that it can be stopped when the window manager quits.",
+                "Restarts are delayed by 1 second, doubling up to 60 seconds if the service keeps exiting.",
+            ],
+            Self::Restart => &[
+                "Set the restart policy that all new services use.",
+                "Policies:",
+                "  - always     : Restart whenever the service exits. (default)",
+                "  - on-failure : Only restart if the service exits unsuccessfully.",
+                "  - never      : Never restart the service.",
+            ],
             Self::KeyAction => &[
                 "Set the current key action that all new key bindings belong to.",
                 "Actions:",
-                "  - quit : End the window manager.",
+                "  - center          : Center the focused floating window on the screen.",
+                "  - enter-mode:NAME : Switch to the key bindings of mode NAME.",
+                "  - grow:EDGE       : Move the EDGE of the focused floating window outwards by `--move-step`.",
+                "  - kill            : Kill the focused window.",
+                "  - leave-mode      : Switch back to the default mode.",
+                "  - move-window     : Drag the window under the pointer until the key is released.",
+                "                      Tiled windows swap places with the tiled window they are dragged onto.",
+                "  - nudge:DIRECTION : Move the focused floating window towards DIRECTION by `--move-step`.",
+                "  - quit            : End the window manager.",
+                "  - reload          : Parse the arguments and config files again, keeping the windows.",
+                "  - resize-window   : Resize the window under the pointer until the key is released.",
+                "                      Floating windows are resized from their nearest corner,",
+                "                      tiled windows move the split of the layout.",
+                "  - shrink:EDGE     : Move the EDGE of the focused floating window inwards by `--move-step`.",
+                "  - snap:REGION     : Fit the focused floating window into a half or quarter of the screen.",
+                "  - toggle-floating : Switch the focused window between tiled and floating.",
+                "Directions and edges are `left`, `right`, `up` and `down`.",
+                "Regions are `left`, `right`, `top`, `bottom`, `top-left`, `top-right`, `bottom-left` and `bottom-right`.",
             ],
             Self::KeySequence => &[
                 "A sequence of keys that executes the current key action",
                 "Syntax:",
                 "  - Most key sequences that can be represented using text simply use text.",
                 "    For example, in order to use the sequence `hello`, the argument would be `-khello`",
-                "  - Keys that cannot be printed, escape them in brackets and use their corresponding code.",
-                "    Codes:",
-                "      - F-{N} : Function key N, where N is a number. (E.g. <F-1> is the f1 key).",
-                "      - PG-UP : Page up.",
-                "      - PG-DN : Page down.",
                 "  - Modifier keys use the the modifier head followed by a dash. (E.g. C-f is control f)",
                 "    Heads:",
                 "      - C : Control.",
                 "      - L : Logo/super.",
                 "      - M : Alt.",
                 "      - S : Shift.",
+                "    Heads followed by `l` or `r` only match the left or right key. (E.g. Lr-f is right super f)",
+                "  - Keys match no matter how they are written, so `A` is the same as `S-a`.",
+                "    Shift cannot be used with characters that have no upper case, such as `S-1`, so bind the character it types instead, such as `!`, or the scan code of the key, such as `S-<SC-2>`.",
+                "  - Keys can be matched by where they are on the keyboard with their scan code, which ignores the keyboard layout.",
+                "    For example, <SC-30> is where the A key is on QWERTY keyboards.",
+                "  - Mouse buttons use <MB-{N}>, where 1 is left, 2 is middle and 3 is right.",
+                "    The scroll wheel uses <WHEEL-UP>, <WHEEL-DOWN>, <WHEEL-LEFT> and <WHEEL-RIGHT>.",
+                "  - Keys that cannot be printed, escape them in brackets and use their corresponding code.",
+                "    Codes:",
+            ],
+            Self::KeyIntercept => &[
+                "Set what happens to the last key of all new key bindings.",
+                "Values:",
+                "  - consume : Hide the key from the focused window. (default)",
+                "  - pass    : Let the key reach the focused window as well, which can happen before the key action is executed.",
+            ],
+            Self::KeyTrigger => &[
+                "Set when all new key bindings execute their key action.",
+                "Repeats of a key never count as pressing it again in a key sequence.",
+                "Values:",
+                "  - press   : Once, when the last key is pressed. (default)",
+                "  - release : Once, when the last key is released.",
+                "  - repeat  : When the last key is pressed, and every time it repeats while held down.",
+            ],
+            Self::KeyMode => &[
+                "Set the mode that all new key bindings belong to.",
+                "Only the key bindings of the active mode are used, which starts as `default`.",
+            ],
+            Self::KeyUnmatched => &[
+                "Set what happens to keys that do not match any key binding in the current mode.",
+                "Values:",
+                "  - allow : Send the key to the focused window. (default)",
+                "  - block : Swallow the key.",
+                "Mouse buttons and the scroll wheel are always sent, so that the mouse can still be used in modes that block keys.",
+            ],
+            Self::KeyTimeout => &[
+                "Set how many milliseconds to wait for the next key of a key sequence.",
+                "Once it runs out the pressed keys are abandoned. 0 waits forever. (default)",
+            ],
+            Self::TimeoutAction => &[
+                "Set the key action that gets executed when a key sequence times out.",
+                "Accepts the same actions as `--key-action`.",
+            ],
+            Self::KeyConflicts => &[
+                "Set what happens when key bindings of the same mode conflict with each other.",
+                "Conflicts are key sequences that are bound twice, key sequences that start with another key sequence,",
+                "and key sequences that the same keys match because only one of them names the side of a modifier (E.g. L-a and Ll-a).",
+                "Values:",
+                "  - warn  : Report conflicts and keep going. (default)",
+                "  - fatal : Report conflicts and exit.",
+            ],
+            Self::MoveStep => &[
+                "Set how many pixels `nudge`, `grow` and `shrink` move floating windows by.",
+                "Defaults to 20.",
             ],
             Self::ConfigFile => &[
                 "Set the config file to parse.",
                 "The default config path depends on the platform, see `--default config` for default path.",
-                "The syntax of the config file is the same as the cli flags, however you can only put 1 flag per line.",
-                "Lines starting with `#` are ignored as comments.",
-                "Examples:",
-                "  Valid:",
+                "The syntax of the config file is the same as the cli flags, split into arguments the way a shell would.",
+                "Arguments are separated by whitespace, so values with whitespace need to be quoted with `'` or `\"`, or escaped with `\\`.",
+                "Inside `\"`, `\\` only escapes `\"` and `\\`, and outside of quotes it only escapes whitespace, quotes, `\\` and `#`.",
+                "A `#` at the start of an argument comments out the rest of the line.",
+                "Example:",
                 "  ```",
-                "  -Kquit",
-                "    -kL-S-Q",
-                "  ```",
-                "  Invalid:",
-                "  ```",
-                "  -Kquit -kL-S-Q",
+                "  -Kquit -kL-S-Q # quit",
+                "  -a 'notepad C:\\notes.txt'",
+                "  --service=\"status bar\"",
                 "  ```",
             ],
+            Self::Include => &[
+                "Apply another config file in place of this flag.",
+                "Relative paths are relative to the directory of the config file that includes them.",
+                "A config file that includes itself, directly or not, is an error.",
+                "Files in the `config.d` directory next to the config file that end with `.txt` are included after it in sorted order.",
+            ],
+            Self::Watch => &[
+                "Reload the configuration when one of its files changes, as if by the `reload` action.",
+                "Changes that fail to parse are reported and the current configuration is kept.",
+            ],
+            Self::Define => &[
+                "Define a variable as `NAME=VALUE`, which later values can use as `$NAME` or `${NAME}`.",
+                "Names may only contain letters, digits and underscores, and cannot start with a digit.",
+                "Variables are looked up before environment variables, and apply to every value including key sequences.",
+                "Example: `-Dmod=L-S-` followed by `-k${mod}Q`.",
+            ],
+            Self::Profile => &[
+                "Set the profile that `--if profile=NAME` checks for.",
+                "This should be given before the config file is applied, such as on the command line.",
+            ],
+            Self::If => &[
+                "Only apply the following flags up to the matching `--end` if a condition holds.",
+                "Flags that are not applied still take their value, and blocks can be nested but not span multiple files.",
+                "Conditions:",
+                "  - os=NAME      : The operating system, such as `windows`, `linux` or `macos`.",
+                "  - host=NAME    : The name of this computer, ignoring case.",
+                "  - profile=NAME : The profile set by `--profile`.",
+                "Use `!=` instead of `=` to check that a condition does not hold.",
+            ],
+            Self::End => &["End the block of the innermost `--if`."],
             Self::PrintDefault => &[
                 "Print the default for a specific configuration option.",
                 "Accepted values:",
@@ -411,7 +1236,33 @@ impl CliFlags {
             ],
         }
     }
-    /// Get the length of [Self::help] with padding and newlines.
+    /// Keys whose codes are listed after [Self::help].
+    const fn help_keys(&self) -> &'static [InvisibleKey] {
+        match self {
+            Self::KeySequence => &InvisibleKey::DOCUMENTED,
+            _ => &[],
+        }
+    }
+    /// The prefix of every line of [Self::help_keys].
+    const HELP_KEY_PREFIX: &str = "      - ";
+    const fn help_keys_max_len(&self) -> usize {
+        let keys = self.help_keys();
+        let mut max = 0;
+        let mut i = 0;
+
+        while i < keys.len() {
+            let len = keys[i].name().len();
+            if len > max {
+                max = len;
+            }
+
+            i += 1;
+        }
+
+        max
+    }
+
+    /// Get the length of [Self::help] and [Self::help_keys] with padding and newlines.
     const fn help_len(&self) -> usize {
         let lines = self.help();
 
@@ -422,6 +1273,19 @@ impl CliFlags {
             i += 1;
         }
 
+        let keys = self.help_keys();
+        let mut i = 0;
+        while i < keys.len() {
+            len += Self::padding_len()
+                + Self::HELP_KEY_PREFIX.len()
+                + self.help_keys_max_len()
+                // ` : `
+                + 3
+                + keys[i].description().len()
+                + 1;
+            i += 1;
+        }
+
         len
     }
     /// Get the sum of all the help messages
@@ -476,11 +1340,25 @@ impl CliFlags {
             string.push('\n');
             i += 1;
         }
+
+        let keys = self.help_keys();
+        let mut i = 0;
+        while i < keys.len() {
+            string.push_str(Self::padding().as_str());
+            string.push_str(Self::HELP_KEY_PREFIX);
+            let name = keys[i].name();
+            string.push_str(name);
+            pad(string, name.len(), self.help_keys_max_len());
+            string.push_str(" : ");
+            string.push_str(keys[i].description());
+            string.push('\n');
+            i += 1;
+        }
     }
 
     fn apply<'a, I, E>(
         &self,
-        config: &mut Config<'a>,
+        config: &mut Config,
         paths: &'a PathCache,
         flag: Flag<'a>,
         argv: &mut Argv<'a, I, E>,
@@ -489,16 +1367,30 @@ impl CliFlags {
         I: Iterator<Item = Result<&'a str, E>>,
         E: Display,
     {
+        // read before `value` takes the rest of the argument
+        let position = argv.args_read();
+        let variables = &config.variables;
         let mut value = move || match argv.value() {
-            Some(Ok(val)) => Ok(val),
+            Some(Ok(val)) => match expand::expand_vars(val, |name| {
+                variables.get(name).cloned().or_else(|| env::var(name).ok())
+            }) {
+                Ok(Cow::Borrowed(val)) => Ok(val),
+                Ok(Cow::Owned(val)) => Ok(paths.keep_str(val)),
+                Err(err) => Err(ApplyError::Expand(err)),
+            },
             Some(Err(err)) => Err(ApplyError::ArgSource(err)),
             None => Err(ApplyError::MissingValue(flag)),
         };
+        let mut expanded = || {
+            value().and_then(|val| {
+                expand::expand_home(val, expand::home_dir).map_err(ApplyError::Expand)
+            })
+        };
 
         match self {
             Self::Help => {
-                const HEAD: &str = "usage: storm [OPTIONS..]\n\n";
-                const TAIL: &str = "";
+                const HEAD: &str = "usage: storm [OPTIONS..] [-- COMMAND [ARGS..]]\n\n";
+                const TAIL: &str = "\nValues expand `$NAME`, `${NAME}` and `${NAME:-default}` from `--define` or the environment, use `$$` for a literal `$`.\nThe values of `--autostart`, `--config`, `--include`, `--log-output` and `--service` also expand a leading `~`.";
                 const TEXT: ConstString<{ HEAD.len() + CliFlags::help_len_all() + TAIL.len() }> = {
                     let mut text = ConstString::new();
                     text.push_str(HEAD);
@@ -544,32 +1436,63 @@ impl CliFlags {
                 }
             }
             Self::LogOutput => {
+                let value = expanded()?;
+                config.log_file = Some(File::open(value.as_ref()).map_err(|err| {
+                    ApplyError::FileOpen(paths.keep(PathBuf::from(value.as_ref())), err)
+                })?);
+                Ok(())
+            }
+            Self::Autostart => {
+                config
+                    .commands
+                    .push(Command::Shell(expanded()?.into_owned()));
+                Ok(())
+            }
+            Self::Service => {
+                let value = expanded()?;
+                // without a shell in between, stopping the service stops the program itself
+                let argv = Tokenizer::new(&value)
+                    .map(|arg| arg.map(Cow::into_owned))
+                    .collect::<Result<SmallVec<_>, _>>()
+                    .map_err(ApplyError::Tokenizer)?;
+                if argv.is_empty() {
+                    return Err(ApplyError::MissingValue(flag));
+                }
+
+                config.services.push(Service {
+                    command: Command::Argv(argv),
+                    restart: config.restart_policy,
+                });
+                Ok(())
+            }
+            Self::Restart => {
                 let value = value()?;
-                config.log_file =
-                    Some(File::open(value).map_err(|err| ApplyError::FileOpen(value, err))?);
+
+                config.restart_policy = lookup(&RESTART_POLICIES, value)
+                    .ok_or(ApplyError::UnknownRestartPolicy(value))?;
                 Ok(())
             }
             Self::KeyAction => {
                 let value = value()?;
 
-                match value {
-                    "kill" => {
-                        config.key_action = Some(KeyAction::Kill);
-                        Ok(())
-                    }
-                    "quit" => {
-                        config.key_action = Some(KeyAction::Quit);
-                        Ok(())
-                    }
-                    _ => Err(ApplyError::UnknownKeyAction(value)),
-                }
+                config.key_action = Some(
+                    config
+                        .parse_key_action(value)
+                        .ok_or(ApplyError::UnknownKeyAction(value))?,
+                );
+                Ok(())
             }
             Self::KeySequence => {
                 if let Some(action) = config.key_action {
                     let value = value()?;
 
-                    if let Some(key_sequence) = KeySequence::parse(value).transpose()? {
-                        config.key_bindings[action].push(key_sequence.0);
+                    if let Some((sequence, _)) = KeySequence::parse(value).transpose()? {
+                        config.modes[config.mode].key_bindings.push(KeyBinding {
+                            action,
+                            intercept: config.key_intercept.unwrap_or(KeyIntercept::Block),
+                            sequence: sequence.into_owned(),
+                            trigger: config.key_trigger,
+                        });
                     }
 
                     Ok(())
@@ -577,11 +1500,144 @@ impl CliFlags {
                     Err(ApplyError::UnsetKeyAction)
                 }
             }
+            Self::KeyIntercept => {
+                let value = value()?;
+
+                config.key_intercept = Some(
+                    lookup(&KEY_INTERCEPTS, value).ok_or(ApplyError::UnknownKeyIntercept(value))?,
+                );
+                Ok(())
+            }
+            Self::KeyTrigger => {
+                let value = value()?;
+
+                config.key_trigger =
+                    lookup(&KEY_TRIGGERS, value).ok_or(ApplyError::UnknownKeyTrigger(value))?;
+                Ok(())
+            }
+            Self::KeyMode => {
+                config.mode = config.modes.get_or_insert(value()?);
+                Ok(())
+            }
+            Self::KeyTimeout => {
+                let value = value()?;
+
+                config.key_timeout = match value
+                    .parse()
+                    .map_err(|err| ApplyError::ParseInt(value, err))?
+                {
+                    0 => None,
+                    timeout => Some(Duration::from_millis(timeout)),
+                };
+                Ok(())
+            }
+            Self::MoveStep => {
+                let value = value()?;
+
+                config.move_step = Some(
+                    value
+                        .parse()
+                        .map_err(|err| ApplyError::ParseInt(value, err))?,
+                );
+                Ok(())
+            }
+            Self::KeyConflicts => {
+                let value = value()?;
+
+                config.key_conflicts = lookup(&CONFLICT_POLICIES, value)
+                    .ok_or(ApplyError::UnknownConflictPolicy(value))?;
+                Ok(())
+            }
+            Self::TimeoutAction => {
+                let value = value()?;
+
+                config.timeout_action = Some(
+                    config
+                        .parse_key_action(value)
+                        .ok_or(ApplyError::UnknownKeyAction(value))?,
+                );
+                Ok(())
+            }
+            Self::KeyUnmatched => {
+                let value = value()?;
+
+                config.modes[config.mode].unmatched =
+                    lookup(&KEY_UNMATCHED, value).ok_or(ApplyError::UnknownKeyUnmatched(value))?;
+                Ok(())
+            }
 
             Self::ConfigFile => {
-                config.config_file = Some(Path::new(value()?));
+                config.config_file = Some(PathBuf::from(expanded()?.as_ref()));
                 Ok(())
             }
+            Self::Include => {
+                let value = expanded()?;
+                let value = Path::new(value.as_ref());
+                let path = match config.files.last().and_then(|file| file.parent()) {
+                    Some(dir) => dir.join(value),
+                    None => value.to_path_buf(),
+                };
+
+                config.include(paths, path)
+            }
+            Self::Watch => {
+                config.watch = true;
+                Ok(())
+            }
+            Self::Define => {
+                let value = value()?;
+
+                match value.split_once('=') {
+                    Some((name, val)) if expand::is_name(name) => {
+                        config.variables.insert(name.to_owned(), val.to_owned());
+                        Ok(())
+                    }
+                    _ => Err(ApplyError::InvalidDefine(value)),
+                }
+            }
+            Self::Profile => {
+                config.profile = Some(value()?.to_owned());
+                Ok(())
+            }
+            Self::If => {
+                let holds = value().and_then(|value| {
+                    let (condition, negated, expected) = match value.split_once("!=") {
+                        Some((condition, expected)) => (condition, true, expected),
+                        None => match value.split_once('=') {
+                            Some((condition, expected)) => (condition, false, expected),
+                            None => return Err(ApplyError::UnknownCondition(value)),
+                        },
+                    };
+
+                    match condition {
+                        "os" => Ok(expected == env::consts::OS),
+                        "host" => Ok(match hostname() {
+                            Ok(host) => host.eq_ignore_ascii_case(expected),
+                            Err(err) => {
+                                config.error(|f| {
+                                    writeln!(f, "failed to get the name of this computer: {}", err)
+                                });
+                                false
+                            }
+                        }),
+                        "profile" => Ok(config.profile.as_deref() == Some(expected)),
+                        _ => Err(ApplyError::UnknownCondition(condition)),
+                    }
+                    .map(|holds| holds != negated)
+                });
+
+                // a block that cannot be checked is still opened so that its flags are skipped
+                // and its `--end` is matched
+                config
+                    .conditions
+                    .push((*holds.as_ref().unwrap_or(&false), position));
+                holds.map(drop)
+            }
+            Self::End => config
+                .conditions
+                .pop()
+                .map(drop)
+                .ok_or(ApplyError::UnmatchedEnd),
             Self::PrintDefault => {
                 let value = value()?;
 
@@ -600,9 +1656,109 @@ impl CliFlags {
     }
 }
 
+/// The directory next to the config file with files that are included after it.
+const DROP_IN_DIR: &str = "config.d";
+
+/// The [DROP_IN_DIR] next to the config file at `path`.
+pub fn drop_in_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(DROP_IN_DIR)
+}
+
+/// The files in `dir` that end with `.txt`, in sorted order.
+///
+/// A missing directory has no files.
+pub fn drop_ins(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut files = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| {
+            path.as_ref().map_or(true, |path| {
+                path.extension().is_some_and(|extension| extension == "txt") && path.is_file()
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    files.sort();
+
+    Ok(files)
+}
+
+/// The name of this computer, which `--if host=NAME` checks for.
+#[cfg(unix)]
+fn hostname() -> io::Result<String> {
+    let mut buffer = [0_u8; 256];
+    // SAFETY: the length is the size of the buffer
+    if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // a name that got cut off is not null terminated
+    let len = buffer
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(buffer.len());
+    Ok(String::from_utf8_lossy(&buffer[..len]).into_owned())
+}
+/// The name of this computer, which `--if host=NAME` checks for.
+#[cfg(windows)]
+fn hostname() -> io::Result<String> {
+    use {
+        std::ptr::null_mut,
+        winapi::um::sysinfoapi::{ComputerNameDnsHostname, GetComputerNameExW},
+    };
+
+    let mut len = 0;
+    // SAFETY: without a buffer this fails, but sets the length that the name needs along with
+    // its null terminator
+    unsafe { GetComputerNameExW(ComputerNameDnsHostname, null_mut(), &mut len) };
+
+    let mut buffer = vec![0; len as usize];
+    // SAFETY: the length is the size of the buffer
+    if unsafe { GetComputerNameExW(ComputerNameDnsHostname, buffer.as_mut_ptr(), &mut len) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // on success the length leaves out the null terminator
+    Ok(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+/// The name of this computer, which `--if host=NAME` checks for.
+#[cfg(not(any(unix, windows)))]
+fn hostname() -> io::Result<String> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Whether `lhs` and `rhs` point to the same file, which compares the paths if either does not
+/// exist.
+fn same_file(lhs: &Path, rhs: &Path) -> bool {
+    match (lhs.canonicalize(), rhs.canonicalize()) {
+        (Ok(lhs), Ok(rhs)) => lhs == rhs,
+        _ => lhs == rhs,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::{
+            config::mode::Mode,
+            layout::{Direction, Region},
+        },
+        std::{env, process},
+    };
+
+    /// The actions of the key bindings in `mode`, in the order they were bound.
+    fn actions(mode: &Mode) -> Vec<KeyAction> {
+        mode.key_bindings
+            .iter()
+            .map(|binding| binding.action)
+            .collect()
+    }
 
     #[test]
     fn flags_exist() {
@@ -654,4 +1810,641 @@ mod tests {
                 assert_eq!(CliFlags::LONG.get(&long), Some(into));
             })
     }
+
+    #[test]
+    fn autostart_commands() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "-afoo bar",
+                    "--autostart=baz",
+                    "-aecho ${STORM_UNSET:-qux} $$1",
+                    "--",
+                    "lorem",
+                    "ipsum",
+                ]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.commands.as_slice(),
+            &[
+                Command::Argv(["lorem", "ipsum"].into_iter().map(str::to_owned).collect()),
+                Command::Shell("foo bar".to_owned()),
+                Command::Shell("baz".to_owned()),
+                Command::Shell("echo qux $1".to_owned()),
+            ]
+        );
+        assert!(matches!(
+            config.apply_args(
+                &paths,
+                ["-a$STORM_UNSET"].into_iter().map(Ok::<_, Infallible>)
+            ),
+            Err(ApplyError::Expand(ExpandError::Unset("STORM_UNSET")))
+        ));
+        // unquoted words after a value are not silently dropped
+        assert!(matches!(
+            config.apply_args(
+                &paths,
+                ["-aecho", "hello", "world"]
+                    .into_iter()
+                    .map(Ok::<_, Infallible>)
+            ),
+            Err(ApplyError::UnexpectedArgument("hello"))
+        ));
+
+        // a command after `--` is only started from the command line
+        let mut config = Config::default();
+        let errors = config
+            .apply_file(
+                &paths,
+                Path::new("config.txt"),
+                "-aecho hello world\n-Kquit -kL-q quit now\n-afoo -- bar\n-abaz\n",
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "config.txt:1: unexpected argument `hello`\n    -aecho hello world",
+                "config.txt:1: unexpected argument `world`\n    -aecho hello world",
+                "config.txt:2: unexpected argument `quit`\n    -Kquit -kL-q quit now",
+                "config.txt:2: unexpected argument `now`\n    -Kquit -kL-q quit now",
+                "config.txt:3: `--` is only allowed on the command line\n    -afoo -- bar",
+            ]
+        );
+        assert_eq!(
+            config.commands.as_slice(),
+            &[
+                Command::Shell("echo".to_owned()),
+                Command::Shell("foo".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_modes() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "-Kenter-mode:resize",
+                    "-kL-r",
+                    "--key-mode=resize",
+                    "-ublock",
+                    "-Kleave-mode",
+                    "-kq",
+                    "-mdefault",
+                    "-Kquit",
+                    "-kL-q",
+                ]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        let resize_id = config.modes.find("resize").unwrap();
+        let [default, resize] = [ModeId::DEFAULT, resize_id].map(|id| &config.modes[id]);
+        assert_eq!(default.unmatched, KeyIntercept::Allow);
+        assert_eq!(resize.unmatched, KeyIntercept::Block);
+        assert_eq!(
+            actions(default),
+            [KeyAction::EnterMode(resize_id), KeyAction::Quit]
+        );
+        assert_eq!(actions(resize), [KeyAction::LeaveMode]);
+
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-Kenter-mode:")]),
+            Err(ApplyError::UnknownKeyAction("enter-mode:"))
+        ));
+    }
+
+    #[test]
+    fn defines() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "-Dmod=L-S-",
+                    "--define=quit=${mod}Q",
+                    "-Kquit",
+                    "-k${mod}E",
+                    "-k$quit",
+                    "-Dmod=L-",
+                    "-k${mod}E",
+                    "-Dshell=echo $$1",
+                    "-a$shell",
+                ]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        let sequences = ["L-S-E", "L-S-Q", "L-E"]
+            .map(|sequence| KeySequence::parse(sequence).unwrap().unwrap().0);
+        assert_eq!(
+            config.modes[ModeId::DEFAULT]
+                .key_bindings
+                .iter()
+                .map(|binding| &binding.sequence)
+                .collect::<Vec<_>>(),
+            sequences.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            config.commands.as_slice(),
+            &[Command::Shell("echo $1".to_owned())]
+        );
+
+        ["-D1mod=L-", "-Dmod", "-D=L-"].into_iter().for_each(|arg| {
+            assert!(matches!(
+                config.apply_args(&paths, [Ok::<_, Infallible>(arg)]),
+                Err(ApplyError::InvalidDefine(_))
+            ));
+        });
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-k${unset}Q")]),
+            Err(ApplyError::Expand(ExpandError::Unset("unset")))
+        ));
+    }
+
+    #[test]
+    fn key_intercept() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                ["-Kquit", "-kq", "-Ipass", "-kw", "--key-intercept=consume", "-ke"]
+                    .into_iter()
+                    .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.modes[ModeId::DEFAULT]
+                .key_bindings
+                .iter()
+                .map(|binding| binding.intercept)
+                .collect::<Vec<_>>(),
+            [KeyIntercept::Block, KeyIntercept::Allow, KeyIntercept::Block]
+        );
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-Ikeep")]),
+            Err(ApplyError::UnknownKeyIntercept("keep"))
+        ));
+    }
+
+    #[test]
+    fn key_trigger() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                ["-Kquit", "-kq", "-rrepeat", "-kw", "--key-trigger=release", "-ke", "-rpress", "-kr"]
+                    .into_iter()
+                    .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.modes[ModeId::DEFAULT]
+                .key_bindings
+                .iter()
+                .map(|binding| binding.trigger)
+                .collect::<Vec<_>>(),
+            [
+                KeyTrigger::Press,
+                KeyTrigger::Repeat,
+                KeyTrigger::Release,
+                KeyTrigger::Press
+            ]
+        );
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-rhold")]),
+            Err(ApplyError::UnknownKeyTrigger("hold"))
+        ));
+    }
+
+    #[test]
+    fn key_timeout() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+        assert_eq!(config.key_timeout, None);
+
+        config
+            .apply_args(
+                &paths,
+                ["-t500", "--timeout-action=leave-mode"]
+                    .into_iter()
+                    .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+        assert_eq!(config.key_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(config.timeout_action, Some(KeyAction::LeaveMode));
+
+        config
+            .apply_args(&paths, [Ok::<_, Infallible>("--key-timeout=0")])
+            .unwrap();
+        assert_eq!(config.key_timeout, None);
+
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-tsoon")]),
+            Err(ApplyError::ParseInt("soon", _))
+        ));
+    }
+
+    #[test]
+    fn file_errors() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        let errors = config
+            .apply_file(
+                &paths,
+                Path::new("config.txt"),
+                "# keys\n-Kquit\n\n--fooo\n-kq\n  -foo\n-rhold\n-Kleave-mode\n-kw\n",
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "config.txt:4: unknown flag `--fooo`\n    --fooo",
+                "config.txt:6: unknown flag `-f`\n    -foo",
+                "config.txt:7: unknown key trigger: hold\n    -rhold",
+            ]
+        );
+        // lines after an error are still applied, and the rest of `-foo` is not read as `-o o`
+        assert!(config.log_file.is_none());
+        assert_eq!(
+            actions(&config.modes[ModeId::DEFAULT]),
+            [KeyAction::Quit, KeyAction::LeaveMode]
+        );
+
+        assert!(
+            config
+                .apply_file(&paths, Path::new("config.txt"), "-Kquit\n-kq\n")
+                .is_ok()
+        );
+
+        // several flags can share a line, quoted values keep their whitespace, and a flag that
+        // fails is reported once without the rest of its argument being read as flags
+        let mut config = Config::default();
+        let errors = config
+            .apply_file(
+                &paths,
+                Path::new("config.txt"),
+                "-kq\n-Kquit -kq -a' foo bar ' # -kw\n-a\"baz\n-rhold -kQ\n",
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "config.txt:1: `key-action` is not set\n    -kq",
+                "config.txt:3: failed to split line into arguments: unclosed quote `\"`\n    -a\"baz",
+                "config.txt:4: unknown key trigger: hold\n    -rhold -kQ",
+            ]
+        );
+        assert_eq!(
+            config.commands.as_slice(),
+            &[Command::Shell(" foo bar ".to_owned())]
+        );
+        assert_eq!(config.modes[ModeId::DEFAULT].key_bindings.len(), 2);
+    }
+
+    #[test]
+    fn conditions() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+        let other_os = match env::consts::OS {
+            "windows" => "linux",
+            _ => "windows",
+        };
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "--profile=work".to_owned(),
+                    format!("--if=os={}", env::consts::OS),
+                    "-Kquit".to_owned(),
+                    "-kq".to_owned(),
+                    "--end".to_owned(),
+                    format!("-Fos={}", other_os),
+                    // the values of skipped flags are not read as flags
+                    "-Kkill".to_owned(),
+                    "-k".to_owned(),
+                    "-w".to_owned(),
+                    "-Fprofile=work".to_owned(),
+                    "-kk".to_owned(),
+                    "-E".to_owned(),
+                    "-E".to_owned(),
+                    "-Fprofile!=home".to_owned(),
+                    "-Fprofile=work".to_owned(),
+                    "-kw".to_owned(),
+                    "-E".to_owned(),
+                    "-E".to_owned(),
+                    format!("--if=host={}", hostname().unwrap().to_uppercase()),
+                    "-kh".to_owned(),
+                    "-E".to_owned(),
+                ]
+                .iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        assert!(!config.watch);
+        assert_eq!(
+            actions(&config.modes[ModeId::DEFAULT]),
+            [KeyAction::Quit, KeyAction::Quit, KeyAction::Quit]
+        );
+
+        let errors = config
+            .apply_file(
+                &paths,
+                Path::new("config.txt"),
+                "--end\n--if=hots=box\n--if=profile=home\n-kk\n",
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "config.txt:1: `--end` is missing a matching `--if`\n    --end",
+                "config.txt:2: unknown condition: hots, did you mean `host`?\n    --if=hots=box",
+                "config.txt:2: `--if` is missing a matching `--end`\n    --if=hots=box",
+            ]
+        );
+        assert!(config.conditions.is_empty());
+        assert_eq!(config.modes[ModeId::DEFAULT].key_bindings.len(), 3);
+
+        // the flags of a block with a misspelled condition are skipped instead of always applied
+        let errors = config
+            .apply_file(
+                &paths,
+                Path::new("config.txt"),
+                "--if=oss=linux\n-Kquit\n-kx\n--end\n",
+            )
+            .unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["config.txt:1: unknown condition: oss, did you mean `os`?\n    --if=oss=linux"]
+        );
+        assert_eq!(config.modes[ModeId::DEFAULT].key_bindings.len(), 3);
+    }
+
+    #[test]
+    fn config_files() {
+        let dir = env::temp_dir().join(format!("storm-config-files-{}", process::id()));
+        let main = dir.join("config.txt");
+        let keys = dir.join("include").join("keys.txt");
+        [
+            ("config.txt", "-Kquit\n-kq\n-iinclude/keys.txt\n"),
+            (
+                "include/keys.txt",
+                "-Kleave-mode\n-kw\n-i../config.txt\n--fooo\n",
+            ),
+            ("config.d/20-b.txt", "-Ktoggle-floating\n-kt\n"),
+            ("config.d/10-a.txt", "-Kkill\n-kk\n"),
+            ("config.d/ignored.conf", "-Kquit\n-kx\n"),
+        ]
+        .into_iter()
+        .for_each(|(name, contents)| {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        });
+
+        let arg = format!("--config={}", main.display());
+        let paths = PathCache::new();
+        let mut config = Config::default();
+        config
+            .apply_args(&paths, [Ok::<_, Infallible>(arg.as_str())])
+            .unwrap();
+        config.args = SmallVec::from_iter([arg.clone()]);
+
+        let errors = config.apply_config_files(&paths).unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [format!(
+                "{}:3: `{}` includes itself\n    -i../config.txt\n{}:4: unknown flag `--fooo`\n    --fooo",
+                keys.display(),
+                keys.parent().unwrap().join("../config.txt").display(),
+                keys.display()
+            )]
+        );
+        assert_eq!(
+            actions(&config.modes[ModeId::DEFAULT]),
+            [
+                KeyAction::Quit,
+                KeyAction::LeaveMode,
+                KeyAction::Kill,
+                KeyAction::ToggleFloating
+            ]
+        );
+        assert!(config.watched.contains(&main));
+        assert!(config.watched.contains(&drop_in_dir(&main)));
+        assert_eq!(
+            drop_ins(&drop_in_dir(&main)).unwrap(),
+            [
+                dir.join("config.d").join("10-a.txt"),
+                dir.join("config.d").join("20-b.txt"),
+            ]
+        );
+        assert!(drop_ins(&drop_in_dir(&keys)).unwrap().is_empty());
+
+        // errors keep the current config
+        assert!(config.reload().is_none());
+        fs::write(&keys, "-Kleave-mode\n-kw\n").unwrap();
+        let reloaded = config.reload().unwrap();
+        assert_eq!(
+            actions(&reloaded.modes[ModeId::DEFAULT]),
+            actions(&config.modes[ModeId::DEFAULT])
+        );
+        assert_eq!(reloaded.args.as_slice(), config.args.as_slice());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn suggestions() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        [
+            (
+                ["--hepl"].as_slice(),
+                "unknown flag `--hepl`, did you mean `--help`?",
+            ),
+            (
+                &["-lverbos"],
+                "unknown log level: verbos, did you mean `verbose`?",
+            ),
+            (
+                &["-dconfg"],
+                "unknown configuration option: confg, did you mean `config`?",
+            ),
+            (
+                &["-Kqiut"],
+                "unknown key action: qiut, did you mean `quit`?",
+            ),
+            (
+                &["-Ksnap:top-lft"],
+                "unknown key action: snap:top-lft, did you mean `snap:top-left`?",
+            ),
+            (
+                &["-Kenter-mod:resize"],
+                "unknown key action: enter-mod:resize, did you mean `enter-mode:resize`?",
+            ),
+            (
+                &["-Kquit", "-k<HOEM>"],
+                "failed to parse keys: unknown special key `HOEM`, did you mean `HOME`?",
+            ),
+            (
+                &["--key-intercept=pas"],
+                "unknown key intercept: pas, did you mean `pass`?",
+            ),
+            (
+                &["--key-trigger=relase"],
+                "unknown key trigger: relase, did you mean `release`?",
+            ),
+            (
+                &["--key-unmatched=alow"],
+                "unknown value for unmatched keys: alow, did you mean `allow`?",
+            ),
+            (
+                &["--key-conflicts=fatl"],
+                "unknown key conflict policy: fatl, did you mean `fatal`?",
+            ),
+            (
+                &["--restart=on-falure"],
+                "unknown restart policy: on-falure, did you mean `on-failure`?",
+            ),
+            (&["--xyz"], "unknown flag `--xyz`"),
+        ]
+        .into_iter()
+        .for_each(|(args, message)| {
+            assert_eq!(
+                config
+                    .apply_args(&paths, args.iter().copied().map(Ok::<_, Infallible>))
+                    .unwrap_err()
+                    .to_string(),
+                message
+            );
+        });
+    }
+
+    #[test]
+    fn key_conflicts() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "-Kquit", "-kC-x", "-kC-xC-c", "-kq", "-kq", "-kL-a", "-kLl-a",
+                ]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+        assert_eq!(config.build_key_tries(), Ok(()));
+
+        config
+            .apply_args(&paths, [Ok::<_, Infallible>("--key-conflicts=fatal")])
+            .unwrap();
+        assert_eq!(config.build_key_tries(), Err(KeyConflictsError(3)));
+
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-Cignore")]),
+            Err(ApplyError::UnknownConflictPolicy("ignore"))
+        ));
+    }
+
+    #[test]
+    fn move_step() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+        assert_eq!(config.move_step(), Config::DEFAULT_MOVE_STEP);
+
+        config
+            .apply_args(
+                &paths,
+                ["-S5", "-Knudge:left", "-kL-h", "--key-action=snap:top-right", "-kL-u"]
+                    .into_iter()
+                    .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+        assert_eq!(config.move_step(), 5);
+        assert_eq!(
+            actions(&config.modes[ModeId::DEFAULT]),
+            [
+                KeyAction::Place(Placement::Nudge(Direction::Left)),
+                KeyAction::Place(Placement::Snap(Region::TopRight)),
+            ]
+        );
+
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-Knudge:sideways")]),
+            Err(ApplyError::UnknownKeyAction("nudge:sideways"))
+        ));
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("--move-step=-1")]),
+            Err(ApplyError::ParseInt("-1", _))
+        ));
+    }
+
+    #[test]
+    fn services() {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+
+        config
+            .apply_args(
+                &paths,
+                [
+                    "-sfoo",
+                    "-Ron-failure",
+                    "-sbar --verbose",
+                    "--restart=never",
+                    "--service=baz -c 'a b'",
+                ]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.services.as_slice(),
+            &[
+                (&["foo"] as &[_], RestartPolicy::Always),
+                (&["bar", "--verbose"], RestartPolicy::OnFailure),
+                (&["baz", "-c", "a b"], RestartPolicy::Never),
+            ]
+            .map(|(argv, restart)| Service {
+                command: Command::Argv(argv.iter().copied().map(str::to_owned).collect()),
+                restart,
+            })
+        );
+        ["--service=", "-s'bar"].into_iter().for_each(|arg| {
+            assert!(config.apply_args(&paths, [Ok::<_, Infallible>(arg)]).is_err());
+        });
+        assert!(matches!(
+            config.apply_args(&paths, [Ok::<_, Infallible>("-Rsometimes")]),
+            Err(ApplyError::UnknownRestartPolicy("sometimes"))
+        ));
+    }
 }
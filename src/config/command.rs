@@ -0,0 +1,88 @@
+use {
+    smallvec::SmallVec,
+    std::{
+        fmt::{self, Display, Formatter},
+        io,
+        process::{self, Child},
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+/// A command that can be spawned as a child process.
+pub enum Command {
+    /// Passed to the platform shell as a single string.
+    Shell(String),
+    /// The program followed by its arguments, spawned without a shell.
+    Argv(SmallVec<[String; 4]>),
+}
+impl Command {
+    /// The shell and the flag that makes it execute the next argument.
+    const SHELL: [&'static str; 2] = if cfg!(windows) {
+        ["cmd", "/C"]
+    } else {
+        ["sh", "-c"]
+    };
+
+    /// Create a [process::Command] that can be spawned any amount of times without borrowing
+    /// `self`.
+    pub fn to_process(&self) -> io::Result<process::Command> {
+        match self {
+            Self::Shell(command) => {
+                let [shell, flag] = Self::SHELL;
+                let mut process = process::Command::new(shell);
+                process.arg(flag).arg(command);
+
+                Ok(process)
+            }
+            Self::Argv(argv) => {
+                let (program, args) = argv
+                    .split_first()
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+                let mut process = process::Command::new(program);
+                process.args(args);
+
+                Ok(process)
+            }
+        }
+    }
+
+    pub fn spawn(&self) -> io::Result<Child> {
+        self.to_process()?.spawn()
+    }
+}
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shell(command) => write!(f, "{}", command),
+            Self::Argv(argv) => argv.iter().enumerate().try_for_each(|(i, arg)| match i {
+                0 => write!(f, "{}", arg),
+                _ => write!(f, " {}", arg),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Determines whether a [Service] gets restarted after it exits.
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    Never,
+}
+impl RestartPolicy {
+    pub const fn should_restart(&self, success: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::OnFailure => !success,
+            Self::Never => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A long running command that is supervised for the lifetime of the window manager.
+pub struct Service {
+    pub command: Command,
+    pub restart: RestartPolicy,
+}
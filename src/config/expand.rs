@@ -0,0 +1,193 @@
+//! Expansion of variables and the home directory in flag values.
+
+use {
+    directories::BaseDirs,
+    std::{
+        borrow::Cow,
+        fmt::{self, Display, Formatter},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpandError<'a> {
+    /// `~` was used, but the home directory could not be found.
+    NoHome,
+    /// A `${` without a matching `}`, along with the rest of the value after it.
+    Unclosed(&'a str),
+    /// A variable without a default that is not set.
+    Unset(&'a str),
+}
+impl Display for ExpandError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoHome => write!(f, "failed to find the home directory for `~`"),
+            Self::Unclosed(rest) => write!(f, "unclosed delimiter `${{`: {}", rest),
+            Self::Unset(name) => write!(f, "variable `{}` is not set", name),
+        }
+    }
+}
+
+/// The home directory of the current user, which `~` expands to.
+pub fn home_dir() -> Option<String> {
+    BaseDirs::new().and_then(|dirs| dirs.home_dir().to_str().map(str::to_owned))
+}
+
+/// Replace a leading `~` with `home()`, if it is by itself or before a path separator.
+pub fn expand_home<'a, F>(value: &'a str, home: F) -> Result<Cow<'a, str>, ExpandError<'a>>
+where
+    F: FnOnce() -> Option<String>,
+{
+    match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let mut home = home().ok_or(ExpandError::NoHome)?;
+            home.push_str(rest);
+            Ok(Cow::Owned(home))
+        }
+        _ => Ok(Cow::Borrowed(value)),
+    }
+}
+
+/// Replace `$NAME` and `${NAME}` with the value of `var(NAME)`, `${NAME:-DEFAULT}` with `DEFAULT`
+/// if the variable is unset or empty, and `$$` with `$`.
+///
+/// A `$` that is not followed by a name is kept as is.
+pub fn expand_vars<'a, F>(value: &'a str, var: F) -> Result<Cow<'a, str>, ExpandError<'a>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = Cow::Borrowed("");
+    let mut rest = value;
+
+    while let Some(dollar) = rest.find('$') {
+        let (head, tail) = rest.split_at(dollar);
+        let tail = &tail[1..];
+        push(&mut output, head);
+
+        rest = if let Some(tail) = tail.strip_prefix('$') {
+            output.to_mut().push('$');
+            tail
+        } else if let Some(braced) = tail.strip_prefix('{') {
+            let end = braced.find('}').ok_or(ExpandError::Unclosed(braced))?;
+            let (name, default) = match braced[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&braced[..end], None),
+            };
+
+            match (
+                var(name).filter(|value| !value.is_empty() || default.is_none()),
+                default,
+            ) {
+                (Some(value), _) => output.to_mut().push_str(&value),
+                (None, Some(default)) => output.to_mut().push_str(default),
+                (None, None) => return Err(ExpandError::Unset(name)),
+            }
+            &braced[end + 1..]
+        } else {
+            let len = name_len(tail);
+            if len == 0 {
+                push(&mut output, "$");
+            } else {
+                let name = &tail[..len];
+                output
+                    .to_mut()
+                    .push_str(&var(name).ok_or(ExpandError::Unset(name))?);
+            }
+            &tail[len..]
+        };
+    }
+    push(&mut output, rest);
+
+    Ok(output)
+}
+
+/// Whether `name` can be referenced as `$NAME`.
+pub fn is_name(name: &str) -> bool {
+    !name.is_empty() && name_len(name) == name.len()
+}
+
+/// Append `text` to `output`, which stays borrowed if it only ever gets one piece of the value.
+fn push<'a>(output: &mut Cow<'a, str>, text: &'a str) {
+    match output {
+        Cow::Borrowed("") => *output = Cow::Borrowed(text),
+        output => output.to_mut().push_str(text),
+    }
+}
+
+/// The length of the variable name at the start of `input`.
+fn name_len(input: &str) -> usize {
+    input
+        .char_indices()
+        .find(|(i, ch)| {
+            !(ch.is_ascii_alphabetic() || *ch == '_' || (*i > 0 && ch.is_ascii_digit()))
+        })
+        .map_or(input.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_values() {
+        let var = |name: &str| match name {
+            "USER" => Some("alice".to_owned()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+
+        [
+            ("plain", Ok("plain")),
+            ("/home/$USER/bin", Ok("/home/alice/bin")),
+            ("${USER}s", Ok("alices")),
+            ("$USER_1", Err(ExpandError::Unset("USER_1"))),
+            ("${MISSING:-default}", Ok("default")),
+            ("${EMPTY:-default}", Ok("default")),
+            ("${EMPTY}", Ok("")),
+            ("${USER:-default}", Ok("alice")),
+            ("$$USER costs $5", Ok("$USER costs $5")),
+            ("trailing $", Ok("trailing $")),
+            ("$MISSING", Err(ExpandError::Unset("MISSING"))),
+            ("${MISSING}", Err(ExpandError::Unset("MISSING"))),
+            ("${USER", Err(ExpandError::Unclosed("USER"))),
+        ]
+        .into_iter()
+        .for_each(|(input, output)| {
+            assert_eq!(
+                expand_vars(input, var).as_deref().map_err(|err| *err),
+                output,
+                "{}",
+                input
+            );
+        });
+        assert!(matches!(
+            expand_vars("plain ~", var),
+            Ok(Cow::Borrowed("plain ~"))
+        ));
+
+        let home = || Some("/home/alice".to_owned());
+        [
+            ("~", Ok("/home/alice")),
+            ("~/config.txt", Ok("/home/alice/config.txt")),
+            ("~alice/config.txt", Ok("~alice/config.txt")),
+            ("a~", Ok("a~")),
+        ]
+        .into_iter()
+        .for_each(|(input, output)| {
+            assert_eq!(
+                expand_home(input, home).as_deref().map_err(|err| *err),
+                output,
+                "{}",
+                input
+            );
+        });
+        assert_eq!(
+            expand_home("~/config.txt", || None),
+            Err(ExpandError::NoHome)
+        );
+
+        assert!(is_name("mod_1"));
+        assert!(!is_name("1mod"));
+        assert!(!is_name("mod-1"));
+        assert!(!is_name(""));
+    }
+}
@@ -1,41 +1,37 @@
-/// Trim a string to make it use less memory
-pub fn trim_string<S: AsRef<str>>(str: S) -> Box<str> {
-    str.as_ref()
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty() && !line.starts_with("#"))
-        .flat_map(|line| [line, "\n"])
-        .collect()
-}
-
 #[derive(Clone, Copy, Debug)]
-pub struct FileParser<'a>(&'a str);
+/// Yields the non empty lines of a config file that are not comments, along with their line
+/// number starting from 1.
+pub struct FileParser<'a> {
+    input: &'a str,
+    /// The number of the line before [Self::input].
+    line: usize,
+}
 impl<'a> FileParser<'a> {
     pub const fn new(input: &'a str) -> Self {
-        Self(input)
+        Self { input, line: 0 }
     }
 }
 impl<'a> From<&'a str> for FileParser<'a> {
     fn from(input: &'a str) -> Self {
-        Self(input)
+        Self::new(input)
     }
 }
 impl<'a> Iterator for FileParser<'a> {
-    type Item = &'a str;
+    type Item = (usize, &'a str);
 
-    fn next(&mut self) -> Option<&'a str> {
-        let next = self
-            .0
-            .lines()
-            .map(|line| line.trim())
-            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        while !self.input.is_empty() {
+            let (line, rest) = self.input.split_once('\n').unwrap_or((self.input, ""));
+            self.input = rest;
+            self.line += 1;
 
-        // SAFETY: `self.0` and `next` point to the same string
-        let position = unsafe { next.as_ptr().offset_from(self.0.as_ptr()) };
-        let position: usize = position.try_into().ok()?;
-        self.0 = &self.0[position + next.len()..];
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                return Some((self.line, line));
+            }
+        }
 
-        Some(next)
+        None
     }
 }
 
@@ -43,28 +39,32 @@ impl<'a> Iterator for FileParser<'a> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn string_flatten() {
-        [["foo\n\nbar\n#baz\n    \nlorem", "foo\nbar\nlorem\n"]]
-            .into_iter()
-            .for_each(|[input, output]| assert_eq!(trim_string(&input).as_ref(), output));
-    }
-
     #[test]
     fn file_parser_iter() {
         [
             (
                 "lorem\nipsum\ndolor\nsit\namet",
-                &["lorem", "ipsum", "dolor", "sit", "amet"] as &[_],
+                &[
+                    (1_usize, "lorem"),
+                    (2, "ipsum"),
+                    (3, "dolor"),
+                    (4, "sit"),
+                    (5, "amet"),
+                ] as &[_],
+            ),
+            ("\t--help\n\t--version", &[(1, "--help"), (2, "--version")]),
+            (
+                "\t--help\n\t--version\n#foobar",
+                &[(1, "--help"), (2, "--version")],
+            ),
+            (
+                "foo\n\nbar\n#baz\n    \r\nlorem\n",
+                &[(1, "foo"), (3, "bar"), (6, "lorem")],
             ),
-            ("\t--help\n\t--version", &["--help", "--version"]),
-            ("\t--help\n\t--version\n#foobar", &["--help", "--version"]),
         ]
         .into_iter()
         .for_each(|(input, output)| {
-            FileParser::new(input)
-                .enumerate()
-                .for_each(|(i, line)| assert_eq!(output[i], line));
+            assert_eq!(FileParser::new(input).collect::<Vec<_>>(), output);
         });
     }
 }
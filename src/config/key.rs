@@ -1,17 +1,24 @@
+pub mod trie;
+
 use {
     crate::{
         backend::{self, Window},
+        config::{mode::ModeId, suggest::Suggestions},
         iter_ext::IterExt,
-        state::Storm,
+        layout::Placement,
+        state::{GrabKind, Storm},
     },
+    either::Either,
     enum_map::{Enum, EnumMap},
+    phf::phf_map,
     smallvec::SmallVec,
     std::{
         borrow::Cow,
         cmp::{Ordering, PartialOrd},
         fmt::{self, Display, Formatter},
+        iter,
         ops::Not,
-        str,
+        str::{self, FromStr},
     },
     strum::VariantArray,
 };
@@ -24,6 +31,9 @@ pub enum ParserError<'a> {
     UnusedEscape { src: &'a str, index: usize },
     UnknownSpecialKey(&'a str),
     UnclosedSpecialKey(&'a str),
+    /// Shift along with a character that has no upper case, which never matches since backends
+    /// report the character that shift types instead.
+    ShiftedCharacter(char),
 }
 impl Display for ParserError<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -31,27 +41,105 @@ impl Display for ParserError<'_> {
             Self::UnusedEscape { src, index } => {
                 write!(f, "unused escape character at index {} of `{}`", index, src)
             }
-            Self::UnknownSpecialKey(key) => write!(f, "unknown special key `{}`", key),
+            Self::UnknownSpecialKey(key) => write!(
+                f,
+                "unknown special key `{}`{}",
+                key,
+                Suggestions::new(
+                    key,
+                    InvisibleKey::NAMES
+                        .keys()
+                        .chain(MouseKey::WHEEL_NAMES.iter())
+                        .copied()
+                )
+            ),
             Self::UnclosedSpecialKey(key) => write!(f, "unclosed delimiter `<`: {}", key),
+            Self::ShiftedCharacter(ch) => {
+                let visible = |ch: char| KeyKind::Visible(Cow::Owned(ch.to_string()));
+                write!(
+                    f,
+                    "`S-{}` depends on the keyboard layout, write the character that shift types",
+                    visible(*ch)
+                )?;
+                match US_SHIFTED.iter().find(|(key, ..)| key == ch) {
+                    Some(&(_, shifted, code)) => write!(
+                        f,
+                        " (`{}` on a US layout) or `S-{}` to match the key wherever it is",
+                        visible(shifted),
+                        ScanCode(code)
+                    ),
+                    None => write!(
+                        f,
+                        " or `S-<SC-N>` with the scan code of the key to match it wherever it is"
+                    ),
+                }
+            }
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Enum)]
+/// The characters that shift types on a US layout and the scan codes of their keys, to suggest
+/// in [ParserError::ShiftedCharacter].
+const US_SHIFTED: [(char, char, u16); 21] = [
+    ('1', '!', 2),
+    ('2', '@', 3),
+    ('3', '#', 4),
+    ('4', '$', 5),
+    ('5', '%', 6),
+    ('6', '^', 7),
+    ('7', '&', 8),
+    ('8', '*', 9),
+    ('9', '(', 10),
+    ('0', ')', 11),
+    ('-', '_', 12),
+    ('=', '+', 13),
+    ('[', '{', 26),
+    (']', '}', 27),
+    (';', ':', 39),
+    ('\'', '"', 40),
+    ('`', '~', 41),
+    ('\\', '|', 43),
+    (',', '<', 51),
+    ('.', '>', 52),
+    ('/', '?', 53),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum KeyAction {
+    /// Switch to the key bindings of another mode.
+    EnterMode(ModeId),
     Kill,
+    /// Switch back to [ModeId::DEFAULT].
+    LeaveMode,
+    /// Make the window under the pointer follow the pointer, see [Storm::grab_window].
+    MoveWindow,
+    /// Move or resize the focused floating window.
+    Place(Placement),
     Quit,
+    /// Parse the configuration again, see [Storm::reload_config].
+    Reload,
+    /// Resize the window under the pointer with the pointer, see [Storm::grab_window].
+    ResizeWindow,
+    /// Switch the focused window between being tiled and floating.
+    ToggleFloating,
 }
 impl KeyAction {
-    pub fn execute<'a, S, W, E>(&self, state: &mut Storm<'a, S, W, E>)
+    pub fn execute<S, W, E>(&self, state: &mut Storm<S, W, E>)
     where
         E: Display,
         S: backend::State<W, E>,
         W: Window,
     {
         match self {
-            Self::Kill => {},
+            Self::EnterMode(mode) => state.set_mode(*mode),
+            Self::Kill => state.kill_focused(),
+            Self::LeaveMode => state.set_mode(ModeId::DEFAULT),
+            Self::MoveWindow => state.grab_window(GrabKind::Move),
+            Self::Place(placement) => state.place_floating(*placement),
             Self::Quit => state.quit = true,
+            Self::Reload => state.reload = true,
+            Self::ResizeWindow => state.grab_window(GrabKind::Resize),
+            Self::ToggleFloating => state.toggle_floating(),
         }
     }
 }
@@ -67,11 +155,68 @@ impl<'a> Key<'a> {
     pub const fn new(mods: KeyModifiers, kind: KeyKind<'a>) -> Self {
         Self { mods, kind }
     }
+
+    pub const fn mods(&self) -> KeyModifiers {
+        self.mods
+    }
+    pub const fn kind(&self) -> &KeyKind<'a> {
+        &self.kind
+    }
+
+    pub fn into_owned(self) -> Key<'static> {
+        Key {
+            mods: self.mods,
+            kind: self.kind.into_owned(),
+        }
+    }
+
+    /// Split `self` into the individual key presses it is made of, see [KeyStroke::normalize].
+    pub fn strokes(&self) -> impl Iterator<Item = KeyStroke> + '_ {
+        self.raw_strokes().map(KeyStroke::normalize)
+    }
+    /// Split `self` into the individual key presses it is made of, without normalizing them.
+    fn raw_strokes(&self) -> impl Iterator<Item = KeyStroke> + '_ {
+        let mods = self.mods;
+
+        match &self.kind {
+            KeyKind::Invisible(key) => Either::Left(iter::once(KeyStroke {
+                mods,
+                kind: KeyStrokeKind::Invisible(*key),
+            })),
+            KeyKind::Physical(code) => Either::Left(iter::once(KeyStroke {
+                mods,
+                kind: KeyStrokeKind::Physical(*code),
+            })),
+            KeyKind::Mouse(key) => Either::Left(iter::once(KeyStroke {
+                mods,
+                kind: KeyStrokeKind::Mouse(*key),
+            })),
+            KeyKind::Visible(keys) => Either::Right(keys.chars().map(move |ch| KeyStroke {
+                mods,
+                kind: KeyStrokeKind::Char(ch),
+            })),
+        }
+    }
+}
+impl From<KeyStroke> for Key<'_> {
+    fn from(stroke: KeyStroke) -> Self {
+        Self::new(
+            stroke.mods,
+            match stroke.kind {
+                KeyStrokeKind::Invisible(key) => KeyKind::Invisible(key),
+                KeyStrokeKind::Physical(code) => KeyKind::Physical(code),
+                KeyStrokeKind::Mouse(key) => KeyKind::Mouse(key),
+                KeyStrokeKind::Char(ch) => KeyKind::Visible(Cow::Owned(ch.into())),
+            },
+        )
+    }
 }
 impl Display for Key<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match &self.kind {
             KeyKind::Invisible(key) => write!(f, "{}{}", self.mods, key),
+            KeyKind::Physical(code) => write!(f, "{}{}", self.mods, code),
+            KeyKind::Mouse(key) => write!(f, "{}{}", self.mods, key),
             KeyKind::Visible(keys) => keys
                 .chars()
                 .try_for_each(|key| write!(f, "{}{}", self.mods, key)),
@@ -89,15 +234,160 @@ impl<'a> Parser<'a> for Key<'a> {
             Ok(o) => o,
             Err(err) => return Some(Err(err)),
         };
+        if modifiers.get(KeyModifier::Shift).is_some()
+            && let KeyKind::Visible(keys) = &kind
+            && let Some(ch) = keys.chars().find(|ch| {
+                !ch.is_whitespace() && !ch.is_control() && ch.to_lowercase().eq(ch.to_uppercase())
+            })
+        {
+            return Some(Err(ParserError::ShiftedCharacter(ch)));
+        }
 
         Some(Ok((Key::new(modifiers, kind), input)))
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A single key press, unlike [Key] which may contain multiple characters.
+pub struct KeyStroke {
+    mods: KeyModifiers,
+    kind: KeyStrokeKind,
+}
+impl KeyStroke {
+    pub const fn mods(&self) -> KeyModifiers {
+        self.mods
+    }
+    pub const fn kind(&self) -> KeyStrokeKind {
+        self.kind
+    }
+
+    /// Turn `self` into the form that every other way of writing it shares, so that keys match
+    /// no matter how they were written or reported by the backend.
+    ///
+    ///  - Control characters that are typed while holding control become their letter.
+    ///  - Characters that have an [InvisibleKey] become that key, such as `\t` and `<TAB>`.
+    ///  - Upper case letters become lower case letters with shift, such as `A` and `S-a`.
+    ///  - Shift is dropped from other characters since they already show whether shift was held,
+    ///    such as `S-!` and `!`.
+    pub fn normalize(mut self) -> Self {
+        let KeyStrokeKind::Char(mut ch) = self.kind else {
+            return self;
+        };
+
+        if self.mods.get(KeyModifier::Control).is_some() && matches!(ch, '\x01'..='\x1a') {
+            ch = char::from(b'a' + ch as u8 - 1);
+        }
+        let invisible = match ch {
+            '\t' => Some(InvisibleKey::Tab),
+            '\n' | '\r' => Some(InvisibleKey::Return),
+            '\x08' => Some(InvisibleKey::Backspace),
+            '\x1b' => Some(InvisibleKey::Escape),
+            ' ' => Some(InvisibleKey::Space),
+            _ => None,
+        };
+        if let Some(key) = invisible {
+            self.kind = KeyStrokeKind::Invisible(key);
+            return self;
+        }
+
+        let mut lower = ch.to_lowercase();
+        match (lower.next(), lower.next()) {
+            (Some(lower), None) if lower != ch => {
+                ch = lower;
+                if self.mods.get(KeyModifier::Shift).is_none() {
+                    self.mods.push(KeyModifier::Shift);
+                }
+            }
+            _ if !ch.is_lowercase() => self.mods.set(KeyModifier::Shift, None),
+            _ => {}
+        }
+        self.kind = KeyStrokeKind::Char(ch);
+
+        self
+    }
+
+    /// The most specific stroke that matches both `self` and `other`, or none if no key press
+    /// matches both of them.
+    pub fn meet(self, other: Self) -> Option<Self> {
+        let meet_side = |a, b| match (a, b) {
+            (KeySide::Any, side) | (side, KeySide::Any) => Some(side),
+            (a, b) => (a == b).then_some(a),
+        };
+
+        let mut meet = self;
+        match (&mut meet.kind, other.kind) {
+            (
+                KeyStrokeKind::Invisible(InvisibleKey::Modifier(modifier, side)),
+                KeyStrokeKind::Invisible(InvisibleKey::Modifier(other_modifier, other_side)),
+            ) if *modifier == other_modifier => *side = meet_side(*side, other_side)?,
+            (kind, other_kind) if *kind == other_kind => {}
+            _ => return None,
+        }
+        for (modifier, side) in self.mods.0 {
+            match (side, other.mods.get(modifier)) {
+                (Some(side), Some(other_side)) => {
+                    meet.mods.set(modifier, Some(meet_side(side, other_side)?))
+                }
+                (None, None) => {}
+                _ => return None,
+            }
+        }
+
+        Some(meet)
+    }
+
+    /// Iterate over the strokes that `self` matches, from the most specific to the least specific
+    /// one: `self` first, then the ones that ignore the side of more and more of its modifiers.
+    ///
+    /// Strokes that ignore the side of as many modifiers come in the order of [KeyModifier], with
+    /// the tapped modifier last, so that the first one that is bound is always the same.
+    pub fn generalizations(self) -> impl Iterator<Item = KeyStroke> {
+        let sided = self
+            .mods
+            .iter_sided()
+            .filter_map(|(modifier, side)| (side != KeySide::Any).then_some(modifier))
+            .collect::<SmallVec<[_; 4]>>();
+        let tap_sided = matches!(
+            self.kind,
+            KeyStrokeKind::Invisible(InvisibleKey::Modifier(_, KeySide::Left | KeySide::Right))
+        );
+
+        let mut masks =
+            (0..1_u8 << (sided.len() + usize::from(tap_sided))).collect::<SmallVec<[_; 32]>>();
+        masks.sort_by_key(|mask| mask.count_ones());
+
+        masks.into_iter().map(move |mask| {
+            let mut stroke = self;
+            sided
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .for_each(|(_, modifier)| stroke.mods.set(*modifier, Some(KeySide::Any)));
+            if let KeyStrokeKind::Invisible(InvisibleKey::Modifier(_, side)) = &mut stroke.kind
+                && mask & (1 << sided.len()) != 0
+            {
+                *side = KeySide::Any;
+            }
+
+            stroke
+        })
+    }
+}
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum KeyStrokeKind {
+    Invisible(InvisibleKey),
+    Physical(ScanCode),
+    Mouse(MouseKey),
+    Char(char),
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum KeyKind<'a> {
     /// Keys that cannot be represented using text (such as `F1`, `PageUp`, ..)
     Invisible(InvisibleKey),
+    /// Keys that are matched by where they are on the keyboard instead of what they type.
+    Physical(ScanCode),
+    Mouse(MouseKey),
     /// Keys that can be represented using text (such as 'a', 'A', 'b', ..)
     Visible(Cow<'a, str>),
 }
@@ -105,6 +395,8 @@ impl Display for KeyKind<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Self::Invisible(key) => write!(f, "{}", key),
+            Self::Physical(code) => write!(f, "{}", code),
+            Self::Mouse(key) => write!(f, "{}", key),
             Self::Visible(key) => key.chars().try_for_each(|ch| match ch {
                 'M' | 'C' | 'S' | 'L' | '<' => write!(f, "\\{}", ch),
                 ch => write!(f, "{}", ch),
@@ -112,6 +404,16 @@ impl Display for KeyKind<'_> {
         }
     }
 }
+impl KeyKind<'_> {
+    pub fn into_owned(self) -> KeyKind<'static> {
+        match self {
+            Self::Invisible(key) => KeyKind::Invisible(key),
+            Self::Physical(code) => KeyKind::Physical(code),
+            Self::Mouse(key) => KeyKind::Mouse(key),
+            Self::Visible(key) => KeyKind::Visible(Cow::Owned(key.into_owned())),
+        }
+    }
+}
 impl<'a> From<Cow<'a, str>> for KeyKind<'a> {
     fn from(key: Cow<'a, str>) -> Self {
         Self::Visible(key)
@@ -126,8 +428,16 @@ impl<'a> Parser<'a> for KeyKind<'a> {
     fn parse(input: &'a str) -> Option<Result<(Self, &'a str), ParserError<'a>>> {
         match input {
             "" => None,
-            input if input.starts_with('<') => InvisibleKey::parse(input)
-                .map(|result| result.map(|(key, next)| (KeyKind::Invisible(key), next))),
+            input if input.starts_with('<') => ScanCode::parse(input)
+                .map(|result| result.map(|(code, next)| (KeyKind::Physical(code), next)))
+                .or_else(|| {
+                    MouseKey::parse(input)
+                        .map(|result| result.map(|(key, next)| (KeyKind::Mouse(key), next)))
+                })
+                .or_else(|| {
+                    InvisibleKey::parse(input)
+                        .map(|result| result.map(|(key, next)| (KeyKind::Invisible(key), next)))
+                }),
             input => {
                 let mut keys = Cow::Borrowed("");
                 let mut chars = input.char_indices().peekable();
@@ -172,12 +482,218 @@ impl<'a> Parser<'a> for KeyKind<'a> {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum InvisibleKey {
     /// Function keys
     F(u8),
+    /// Number keys on the numpad
+    Numpad(u8),
+    /// A modifier key by itself.
+    ///
+    /// Backends report presses and releases of modifier keys with this, which [Storm] turns into
+    /// a key press of its own when nothing else was pressed in between.
+    Modifier(KeyModifier, KeySide),
     PageUp,
     PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    Escape,
+    Tab,
+    Return,
+    Backspace,
+    Space,
+    PrintScreen,
+    Pause,
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    MediaPlayPause,
+    MediaStop,
+    MediaNext,
+    MediaPrevious,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    BrightnessUp,
+    BrightnessDown,
+}
+impl InvisibleKey {
+    /// Every kind of key in the order of the help message, where [Self::F], [Self::Numpad] and
+    /// [Self::Modifier] stand in for all of their values.
+    pub const DOCUMENTED: [Self; 37] = [
+        Self::F(0),
+        Self::Numpad(0),
+        Self::Modifier(KeyModifier::Super, KeySide::Any),
+        Self::PageUp,
+        Self::PageDown,
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::Home,
+        Self::End,
+        Self::Insert,
+        Self::Delete,
+        Self::Escape,
+        Self::Tab,
+        Self::Return,
+        Self::Backspace,
+        Self::Space,
+        Self::PrintScreen,
+        Self::Pause,
+        Self::CapsLock,
+        Self::NumLock,
+        Self::ScrollLock,
+        Self::NumpadAdd,
+        Self::NumpadSubtract,
+        Self::NumpadMultiply,
+        Self::NumpadDivide,
+        Self::NumpadDecimal,
+        Self::MediaPlayPause,
+        Self::MediaStop,
+        Self::MediaNext,
+        Self::MediaPrevious,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::VolumeMute,
+        Self::BrightnessUp,
+        Self::BrightnessDown,
+    ];
+    /// Keys without a value, by [Self::name].
+    const NAMES: phf::Map<&str, Self> = phf_map! {
+        "PG-UP" => Self::PageUp,
+        "PG-DN" => Self::PageDown,
+        "UP" => Self::Up,
+        "DOWN" => Self::Down,
+        "LEFT" => Self::Left,
+        "RIGHT" => Self::Right,
+        "HOME" => Self::Home,
+        "END" => Self::End,
+        "INS" => Self::Insert,
+        "DEL" => Self::Delete,
+        "ESC" => Self::Escape,
+        "TAB" => Self::Tab,
+        "RET" => Self::Return,
+        "BS" => Self::Backspace,
+        "SPC" => Self::Space,
+        "PRINT" => Self::PrintScreen,
+        "PAUSE" => Self::Pause,
+        "CAPS" => Self::CapsLock,
+        "NUM-LOCK" => Self::NumLock,
+        "SCROLL-LOCK" => Self::ScrollLock,
+        "KP-ADD" => Self::NumpadAdd,
+        "KP-SUB" => Self::NumpadSubtract,
+        "KP-MUL" => Self::NumpadMultiply,
+        "KP-DIV" => Self::NumpadDivide,
+        "KP-DEC" => Self::NumpadDecimal,
+        "PLAY" => Self::MediaPlayPause,
+        "STOP" => Self::MediaStop,
+        "NEXT" => Self::MediaNext,
+        "PREV" => Self::MediaPrevious,
+        "VOL-UP" => Self::VolumeUp,
+        "VOL-DN" => Self::VolumeDown,
+        "MUTE" => Self::VolumeMute,
+        "BRIGHT-UP" => Self::BrightnessUp,
+        "BRIGHT-DN" => Self::BrightnessDown,
+    };
+
+    /// The code used inside of brackets, where the value of [Self::F], [Self::Numpad] and
+    /// [Self::Modifier] is written as `{N}` or `{M}`.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::F(_) => "F-{N}",
+            Self::Numpad(_) => "KP-{N}",
+            Self::Modifier(..) => "TAP-{M}",
+            Self::PageUp => "PG-UP",
+            Self::PageDown => "PG-DN",
+            Self::Up => "UP",
+            Self::Down => "DOWN",
+            Self::Left => "LEFT",
+            Self::Right => "RIGHT",
+            Self::Home => "HOME",
+            Self::End => "END",
+            Self::Insert => "INS",
+            Self::Delete => "DEL",
+            Self::Escape => "ESC",
+            Self::Tab => "TAB",
+            Self::Return => "RET",
+            Self::Backspace => "BS",
+            Self::Space => "SPC",
+            Self::PrintScreen => "PRINT",
+            Self::Pause => "PAUSE",
+            Self::CapsLock => "CAPS",
+            Self::NumLock => "NUM-LOCK",
+            Self::ScrollLock => "SCROLL-LOCK",
+            Self::NumpadAdd => "KP-ADD",
+            Self::NumpadSubtract => "KP-SUB",
+            Self::NumpadMultiply => "KP-MUL",
+            Self::NumpadDivide => "KP-DIV",
+            Self::NumpadDecimal => "KP-DEC",
+            Self::MediaPlayPause => "PLAY",
+            Self::MediaStop => "STOP",
+            Self::MediaNext => "NEXT",
+            Self::MediaPrevious => "PREV",
+            Self::VolumeUp => "VOL-UP",
+            Self::VolumeDown => "VOL-DN",
+            Self::VolumeMute => "MUTE",
+            Self::BrightnessUp => "BRIGHT-UP",
+            Self::BrightnessDown => "BRIGHT-DN",
+        }
+    }
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::F(_) => "Function key N, where N is a number. (E.g. <F-1> is the f1 key).",
+            Self::Numpad(_) => "Numpad key N, where N is a digit.",
+            Self::Modifier(..) => {
+                "Tapping modifier M alone, where M is a head. (E.g. <TAP-L> is tapping super)."
+            }
+            Self::PageUp => "Page up.",
+            Self::PageDown => "Page down.",
+            Self::Up => "Up arrow.",
+            Self::Down => "Down arrow.",
+            Self::Left => "Left arrow.",
+            Self::Right => "Right arrow.",
+            Self::Home => "Home.",
+            Self::End => "End.",
+            Self::Insert => "Insert.",
+            Self::Delete => "Delete.",
+            Self::Escape => "Escape.",
+            Self::Tab => "Tab.",
+            Self::Return => "Return/enter.",
+            Self::Backspace => "Backspace.",
+            Self::Space => "Space bar.",
+            Self::PrintScreen => "Print screen.",
+            Self::Pause => "Pause/break.",
+            Self::CapsLock => "Caps lock.",
+            Self::NumLock => "Num lock.",
+            Self::ScrollLock => "Scroll lock.",
+            Self::NumpadAdd => "Numpad plus.",
+            Self::NumpadSubtract => "Numpad minus.",
+            Self::NumpadMultiply => "Numpad asterisk.",
+            Self::NumpadDivide => "Numpad slash.",
+            Self::NumpadDecimal => "Numpad decimal point.",
+            Self::MediaPlayPause => "Play/pause media.",
+            Self::MediaStop => "Stop media.",
+            Self::MediaNext => "Next track.",
+            Self::MediaPrevious => "Previous track.",
+            Self::VolumeUp => "Raise volume.",
+            Self::VolumeDown => "Lower volume.",
+            Self::VolumeMute => "Mute volume.",
+            Self::BrightnessUp => "Raise screen brightness. (never reported on windows)",
+            Self::BrightnessDown => "Lower screen brightness. (never reported on windows)",
+        }
+    }
 }
 impl Display for InvisibleKey {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -185,8 +701,9 @@ impl Display for InvisibleKey {
 
         match self {
             Self::F(n) => write!(f, "F-{n}"),
-            Self::PageUp => write!(f, "PG-UP"),
-            Self::PageDown => write!(f, "PG-DN"),
+            Self::Numpad(n) => write!(f, "KP-{n}"),
+            Self::Modifier(modifier, side) => write!(f, "TAP-{}{}", modifier.head(), side),
+            key => write!(f, "{}", key.name()),
         }?;
 
         write!(f, ">")
@@ -197,27 +714,124 @@ impl<'a> Parser<'a> for InvisibleKey {
         if input.is_empty() || !input.starts_with('<') {
             None
         } else if let Some(end) = input.find('>') {
-            let next = &input[end..];
-
-            match &input[1..end] {
-                "PG-UP" => Some(Ok((InvisibleKey::PageUp, next))),
-                "PG-DN" => Some(Ok((InvisibleKey::PageDown, next))),
-                fkey if fkey.starts_with("F-") => fkey
-                    .chars()
-                    .skip(2)
-                    .try_fold(0_u8, |fold, next| {
-                        let err = || ParserError::UnknownSpecialKey(fkey);
-
-                        fold.checked_mul(10)
-                            .ok_or_else(err)?
-                            .checked_add(next.to_digit(10).ok_or_else(err)? as u8)
-                            .ok_or_else(err)
+            let code = &input[1..end];
+            let next = &input[end + 1..];
+
+            Some(
+                Self::NAMES
+                    .get(code)
+                    .copied()
+                    .map(Ok)
+                    .or_else(|| parse_numbered(code, "F-", Self::F))
+                    .or_else(|| parse_numbered(code, "KP-", Self::Numpad))
+                    .or_else(|| {
+                        code.strip_prefix("TAP-").map(|head| {
+                            match KeyModifier::parse_head(head) {
+                                Some((modifier, side, "")) => Ok(Self::Modifier(modifier, side)),
+                                _ => Err(ParserError::UnknownSpecialKey(code)),
+                            }
+                        })
                     })
-                    .map(|i| (InvisibleKey::F(i), next))
-                    .map(Some)
-                    .transpose(),
-                unknown => Some(Err(ParserError::UnknownSpecialKey(unknown))),
-            }
+                    .unwrap_or(Err(ParserError::UnknownSpecialKey(code)))
+                    .map(|key| (key, next)),
+            )
+        } else {
+            Some(Err(ParserError::UnclosedSpecialKey(input)))
+        }
+    }
+}
+
+/// Parse the number after the `prefix` of numbered keys such as [InvisibleKey::F].
+fn parse_numbered<'a, T, K>(
+    code: &'a str,
+    prefix: &str,
+    key: fn(T) -> K,
+) -> Option<Result<K, ParserError<'a>>>
+where
+    T: FromStr,
+{
+    let digits = code.strip_prefix(prefix)?;
+
+    Some(
+        digits
+            .bytes()
+            .all(|digit| digit.is_ascii_digit())
+            .then(|| digits.parse().ok())
+            .flatten()
+            .map(key)
+            .ok_or(ParserError::UnknownSpecialKey(code)),
+    )
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The scan code of a key, which is the same for every keyboard layout.
+///
+/// Extended keys have `0xe000` added to their scan code.
+pub struct ScanCode(pub u16);
+impl Display for ScanCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<SC-{}>", self.0)
+    }
+}
+impl<'a> Parser<'a> for ScanCode {
+    fn parse(input: &'a str) -> Option<Result<(Self, &'a str), ParserError<'a>>> {
+        if !input.starts_with("<SC-") {
+            None
+        } else if let Some(end) = input.find('>') {
+            parse_numbered(&input[1..end], "SC-", ScanCode)
+                .map(|result| result.map(|code| (code, &input[end + 1..])))
+        } else {
+            Some(Err(ParserError::UnclosedSpecialKey(input)))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// Buttons and scroll directions of a mouse.
+///
+/// Scrolling is never released, so it only triggers key bindings on press.
+pub enum MouseKey {
+    /// Mouse button N, where 1 is the left button, 2 is the middle button and 3 is the right
+    /// button.
+    Button(u8),
+    WheelUp,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+}
+impl MouseKey {
+    /// Names of the scroll directions between `<>`.
+    const WHEEL_NAMES: [&str; 4] = ["WHEEL-UP", "WHEEL-DOWN", "WHEEL-LEFT", "WHEEL-RIGHT"];
+}
+impl Display for MouseKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Button(n) => write!(f, "<MB-{}>", n),
+            Self::WheelUp => write!(f, "<WHEEL-UP>"),
+            Self::WheelDown => write!(f, "<WHEEL-DOWN>"),
+            Self::WheelLeft => write!(f, "<WHEEL-LEFT>"),
+            Self::WheelRight => write!(f, "<WHEEL-RIGHT>"),
+        }
+    }
+}
+impl<'a> Parser<'a> for MouseKey {
+    fn parse(input: &'a str) -> Option<Result<(Self, &'a str), ParserError<'a>>> {
+        if !input.starts_with("<MB-") && !input.starts_with("<WHEEL-") {
+            None
+        } else if let Some(end) = input.find('>') {
+            let code = &input[1..end];
+
+            Some(
+                match code {
+                    "WHEEL-UP" => Ok(Self::WheelUp),
+                    "WHEEL-DOWN" => Ok(Self::WheelDown),
+                    "WHEEL-LEFT" => Ok(Self::WheelLeft),
+                    "WHEEL-RIGHT" => Ok(Self::WheelRight),
+                    code => parse_numbered(code, "MB-", Self::Button)
+                        .unwrap_or(Err(ParserError::UnknownSpecialKey(code))),
+                }
+                .map(|key| (key, &input[end + 1..])),
+            )
         } else {
             Some(Err(ParserError::UnclosedSpecialKey(input)))
         }
@@ -253,10 +867,28 @@ impl KeySequence<'_> {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_owned(self) -> KeySequence<'static> {
+        KeySequence(self.0.into_iter().map(Key::into_owned).collect())
+    }
 }
 impl<'a> KeySequence<'a> {
-    /// Add a new key or append to the current tail if they share modifiers and are both textual
+    /// Add a new key or append to the current tail if they share modifiers and are both textual.
+    ///
+    /// Keys are stored in the form of [KeyStroke::normalize].
     pub fn push(&mut self, key: Key<'a>) {
+        if key.raw_strokes().eq(key.strokes()) {
+            self.push_normalized(key);
+        } else {
+            key.strokes()
+                .for_each(|stroke| self.push_normalized(stroke.into()));
+        }
+    }
+    fn push_normalized(&mut self, key: Key<'a>) {
         match (self.0.last_mut(), key) {
             (
                 Some(Key {
@@ -356,10 +988,10 @@ impl PartialOrd for KeySequence<'_> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Enum, PartialEq, VariantArray)]
+#[derive(Clone, Copy, Debug, Enum, Eq, Hash, PartialEq, VariantArray)]
 /// The possible modifier keys from a key press.
 ///
-/// Does not distinguish between left and right variants.
+/// Which of the left and right variants is meant is stored separately as a [KeySide].
 pub enum KeyModifier {
     /// AKA meta key.
     Alt,
@@ -377,6 +1009,31 @@ impl KeyModifier {
             Self::Super => "L-",
         }
     }
+    const fn head(&self) -> char {
+        match self {
+            Self::Alt => 'M',
+            Self::Control => 'C',
+            Self::Shift => 'S',
+            Self::Super => 'L',
+        }
+    }
+
+    /// Parse a head followed by an optional side, such as `L` or `Ll`.
+    fn parse_head(input: &str) -> Option<(KeyModifier, KeySide, &str)> {
+        let modifier = match input.as_bytes().first()? {
+            b'M' => KeyModifier::Alt,
+            b'C' => KeyModifier::Control,
+            b'S' => KeyModifier::Shift,
+            b'L' => KeyModifier::Super,
+            _ => return None,
+        };
+
+        Some(match input.as_bytes().get(1) {
+            Some(b'l') => (modifier, KeySide::Left, &input[2..]),
+            Some(b'r') => (modifier, KeySide::Right, &input[2..]),
+            _ => (modifier, KeySide::Any, &input[1..]),
+        })
+    }
 }
 impl Display for KeyModifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -395,31 +1052,72 @@ impl<'a> Parser<'a> for KeyModifier {
     }
 }
 
-#[derive(Debug, Default, PartialEq)]
-pub struct KeyModifiers(EnumMap<KeyModifier, bool>);
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+/// Which of the two keys of a [KeyModifier] is held down.
+pub enum KeySide {
+    /// Either key, or both of them.
+    #[default]
+    Any,
+    Left,
+    Right,
+}
+impl Display for KeySide {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => Ok(()),
+            Self::Left => write!(f, "l"),
+            Self::Right => write!(f, "r"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct KeyModifiers(EnumMap<KeyModifier, Option<KeySide>>);
 impl KeyModifiers {
-    pub fn from_fn<F>(f: F) -> Self
+    pub fn from_fn<F>(mut f: F) -> Self
     where
         F: FnMut(KeyModifier) -> bool,
     {
-        Self(EnumMap::from_fn(f))
+        Self(EnumMap::from_fn(|modifier| f(modifier).then_some(KeySide::Any)))
     }
 
     /// Returns whether there are any active key modifiers
     pub fn is_active(&self) -> bool {
-        self.0.values().copied().any(|active| active)
+        self.0.values().any(Option::is_some)
+    }
+
+    /// Iterate over the active key modifiers
+    pub fn iter(&self) -> impl Iterator<Item = KeyModifier> + '_ {
+        self.iter_sided().map(|(modifier, _)| modifier)
+    }
+    /// Iterate over the active key modifiers along with which of their keys are held down.
+    pub fn iter_sided(&self) -> impl Iterator<Item = (KeyModifier, KeySide)> + '_ {
+        self.0
+            .iter()
+            .filter_map(|(modifier, side)| side.map(|side| (modifier, side)))
     }
 
     pub fn push(&mut self, modifier: KeyModifier) {
-        self.0[modifier] = true;
+        self.push_sided(modifier, KeySide::Any);
+    }
+    /// Activate `modifier`, which becomes [KeySide::Any] if the other side is already active.
+    pub fn push_sided(&mut self, modifier: KeyModifier, side: KeySide) {
+        self.0[modifier] = match self.0[modifier] {
+            Some(active) if active != side => Some(KeySide::Any),
+            _ => Some(side),
+        };
+    }
+    pub fn get(&self, modifier: KeyModifier) -> Option<KeySide> {
+        self.0[modifier]
+    }
+    pub fn set(&mut self, modifier: KeyModifier, side: Option<KeySide>) {
+        self.0[modifier] = side;
     }
 }
 impl Display for KeyModifiers {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.0
-            .iter()
-            .filter_map(|(modifier, active)| active.then_some(modifier))
-            .try_for_each(|modifier| write!(f, "{}", modifier))
+        self.iter_sided()
+            .try_for_each(|(modifier, side)| write!(f, "{}{}-", modifier.head(), side))
     }
 }
 impl Extend<KeyModifier> for KeyModifiers {
@@ -430,6 +1128,26 @@ impl Extend<KeyModifier> for KeyModifiers {
         iter.into_iter().for_each(|key_mod| self.push(key_mod))
     }
 }
+impl Extend<(KeyModifier, KeySide)> for KeyModifiers {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (KeyModifier, KeySide)>,
+    {
+        iter.into_iter()
+            .for_each(|(key_mod, side)| self.push_sided(key_mod, side))
+    }
+}
+impl FromIterator<(KeyModifier, KeySide)> for KeyModifiers {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (KeyModifier, KeySide)>,
+    {
+        let mut output = Self::default();
+        output.extend(iter);
+
+        output
+    }
+}
 impl FromIterator<(KeyModifier, bool)> for KeyModifiers {
     fn from_iter<T>(iter: T) -> Self
     where
@@ -457,10 +1175,11 @@ impl<'a> Parser<'a> for KeyModifiers {
         let mut some = false;
         let mut key_mods = KeyModifiers::default();
 
-        while let Some((key_mod, next_input)) = KeyModifier::parse(input).transpose().expect("internal error: the implementation of [KeyModifierParser::parse] should never return any errors")
+        while let Some((key_mod, side, next_input)) = KeyModifier::parse_head(input)
+            .and_then(|(key_mod, side, next)| Some((key_mod, side, next.strip_prefix('-')?)))
         {
             some = true;
-            key_mods.push(key_mod);
+            key_mods.push_sided(key_mod, side);
 
             input = next_input;
         }
@@ -659,10 +1378,65 @@ mod tests {
     #[test]
     fn invisible_key() {
         test_parser((u8::MIN..=u8::MAX).map(|i| (format!("<F-{}>", i), InvisibleKey::F(i))));
-        test_parser([
-            ("<PG-UP>", InvisibleKey::PageUp),
-            ("<PG-DN>", InvisibleKey::PageDown),
-        ]);
+        test_parser(
+            (u8::MIN..=u8::MAX).map(|i| (format!("<KP-{}>", i), InvisibleKey::Numpad(i))),
+        );
+        test_parser(
+            InvisibleKey::DOCUMENTED
+                .iter()
+                .copied()
+                .filter(|key| {
+                    !matches!(
+                        key,
+                        InvisibleKey::F(_) | InvisibleKey::Numpad(_) | InvisibleKey::Modifier(..)
+                    )
+                })
+                .map(|key| (format!("<{}>", key.name()), key)),
+        );
+        test_parser(
+            KeyModifier::VARIANTS
+                .iter()
+                .copied()
+                .cartesian_product([KeySide::Any, KeySide::Left, KeySide::Right])
+                .map(|(modifier, side)| {
+                    (
+                        format!("<TAP-{}{}>", modifier.head(), side),
+                        InvisibleKey::Modifier(modifier, side),
+                    )
+                }),
+        );
+        assert_eq!(
+            InvisibleKey::NAMES.len() + 3,
+            InvisibleKey::DOCUMENTED.len()
+        );
+
+        ["<F->", "<KP->", "<KP-X>", "<F-256>", "<HOME-1>", "<TAP-X>", "<TAP-Lx>"]
+            .into_iter()
+            .for_each(|input| {
+                assert_eq!(
+                    InvisibleKey::parse(input),
+                    Some(Err(ParserError::UnknownSpecialKey(
+                        &input[1..input.len() - 1]
+                    )))
+                )
+            });
+        assert_eq!(
+            KeySequence::parse("<UP>k<VOL-UP>"),
+            Some(Ok((
+                KeySequence::from_iter([
+                    Key::new(
+                        KeyModifiers::default(),
+                        KeyKind::Invisible(InvisibleKey::Up)
+                    ),
+                    Key::new(KeyModifiers::default(), "k".into()),
+                    Key::new(
+                        KeyModifiers::default(),
+                        KeyKind::Invisible(InvisibleKey::VolumeUp)
+                    ),
+                ]),
+                ""
+            )))
+        );
     }
 
     #[test]
@@ -690,6 +1464,139 @@ mod tests {
         );
     }
 
+    #[test]
+    fn key_modifier_sides() {
+        let sided = KeyModifiers::from_iter([
+            (KeyModifier::Control, KeySide::Left),
+            (KeyModifier::Super, KeySide::Right),
+            (KeyModifier::Shift, KeySide::Any),
+        ]);
+        test_parser([("Cl-Lr-S-", sided)]);
+
+        let mut both = KeyModifiers::default();
+        both.push_sided(KeyModifier::Alt, KeySide::Left);
+        both.push_sided(KeyModifier::Alt, KeySide::Right);
+        assert_eq!(both, KeyModifiers::from_iter([KeyModifier::Alt]));
+
+        let stroke = Key::new(
+            KeyModifiers::from_iter([(KeyModifier::Control, KeySide::Left)]),
+            KeyKind::Invisible(InvisibleKey::Modifier(KeyModifier::Super, KeySide::Right)),
+        )
+        .strokes()
+        .next()
+        .unwrap();
+        assert_eq!(
+            stroke
+                .generalizations()
+                .map(|stroke| Key::from(stroke).to_string())
+                .collect::<Vec<_>>(),
+            ["Cl-<TAP-Lr>", "C-<TAP-Lr>", "Cl-<TAP-L>", "C-<TAP-L>"]
+        );
+    }
+
+    #[test]
+    fn scan_code() {
+        test_parser((u16::MIN..=u16::MAX).map(|i| (format!("<SC-{}>", i), ScanCode(i))));
+        ["<SC->", "<SC-+1>", "<SC-65536>", "<SC-a>"]
+            .into_iter()
+            .for_each(|input| {
+                assert_eq!(
+                    ScanCode::parse(input),
+                    Some(Err(ParserError::UnknownSpecialKey(
+                        &input[1..input.len() - 1]
+                    )))
+                )
+            });
+        assert_eq!(
+            KeySequence::parse("L-<SC-30>"),
+            Some(Ok((
+                KeySequence::from_iter([Key::new(
+                    KeyModifiers::from_iter([KeyModifier::Super]),
+                    KeyKind::Physical(ScanCode(30))
+                )]),
+                ""
+            )))
+        );
+    }
+
+    #[test]
+    fn mouse_key() {
+        test_parser((u8::MIN..=u8::MAX).map(|i| (format!("<MB-{}>", i), MouseKey::Button(i))));
+        test_parser([
+            ("<WHEEL-UP>", MouseKey::WheelUp),
+            ("<WHEEL-DOWN>", MouseKey::WheelDown),
+            ("<WHEEL-LEFT>", MouseKey::WheelLeft),
+            ("<WHEEL-RIGHT>", MouseKey::WheelRight),
+        ]);
+        assert_eq!(
+            MouseKey::parse("<WHEEL-IN>"),
+            Some(Err(ParserError::UnknownSpecialKey("WHEEL-IN")))
+        );
+        assert_eq!(
+            KeySequence::parse("L-<WHEEL-UP>L-<MB-1>"),
+            Some(Ok((
+                KeySequence::from_iter(
+                    [MouseKey::WheelUp, MouseKey::Button(1)].map(|key| Key::new(
+                        KeyModifiers::from_iter([KeyModifier::Super]),
+                        KeyKind::Mouse(key)
+                    ))
+                ),
+                ""
+            )))
+        );
+    }
+
+    #[test]
+    fn key_normalize() {
+        let sequence = |input| KeySequence::parse(input).unwrap().unwrap().0;
+
+        [
+            ("A", "S-a"),
+            ("S-A", "S-a"),
+            ("C-A", "C-S-a"),
+            ("fooB", "fooS-b"),
+            ("a\\tb", "a<TAB>b"),
+            ("hello world", "hello<SPC>world"),
+            ("S-<TAB>", "S-<TAB>"),
+        ]
+        .into_iter()
+        .for_each(|(input, expected)| {
+            assert_eq!(sequence(input), sequence(expected));
+            assert_eq!(sequence(input).to_string(), expected);
+        });
+
+        ["S-1", "C-S-!", "S-a1"].into_iter().for_each(|input| {
+            assert!(matches!(
+                KeySequence::parse(input),
+                Some(Err(ParserError::ShiftedCharacter(_)))
+            ));
+        });
+        assert_eq!(
+            ParserError::ShiftedCharacter(',').to_string(),
+            "`S-,` depends on the keyboard layout, write the character that shift types (`\\<` \
+             on a US layout) or `S-<SC-51>` to match the key wherever it is"
+        );
+        assert_eq!(
+            sequence("S-<SC-2>"),
+            KeySequence::from_iter([Key::new(
+                KeyModifiers::from_iter([KeyModifier::Shift]),
+                KeyKind::Physical(ScanCode(2))
+            )])
+        );
+
+        // what backends report while control is held
+        let control = KeyModifiers::from_iter([KeyModifier::Control]);
+        assert_eq!(
+            KeySequence::from_iter([Key::new(control, "\x01".into())]),
+            sequence("C-a")
+        );
+        assert!(
+            Key::new(control, "\x01".into())
+                .strokes()
+                .eq(sequence("C-a").iter().flat_map(Key::strokes))
+        );
+    }
+
     #[test]
     fn key_kind() {
         assert_eq!(
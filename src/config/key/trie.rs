@@ -0,0 +1,227 @@
+use {
+    super::{Key, KeySequence, KeyStroke},
+    std::collections::HashMap,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// A position inside of a [KeyTrie], which represents the keys that were pressed so far.
+pub struct NodeId(usize);
+impl NodeId {
+    /// The node where no keys have been pressed yet.
+    pub const ROOT: Self = Self(0);
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    children: HashMap<KeyStroke, NodeId>,
+    value: Option<T>,
+}
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: HashMap::new(),
+            value: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The result of advancing through a [KeyTrie].
+pub enum KeyMatch<'a, T> {
+    /// The pressed keys are bound to a value.
+    Complete(&'a T),
+    /// The pressed keys are the start of at least one key sequence.
+    Prefix(NodeId),
+    /// No key sequences start with the pressed keys.
+    None,
+}
+
+#[derive(Debug)]
+/// Prefix tree of [KeySequence]s, so that each key press can be resolved in time proportional to
+/// the length of the key.
+pub struct KeyTrie<T>(Vec<Node<T>>);
+impl<T> Default for KeyTrie<T> {
+    fn default() -> Self {
+        Self(Vec::from([Node::default()]))
+    }
+}
+impl<T> KeyTrie<T> {
+    /// Bind `sequence` to `value`.
+    ///
+    /// If `sequence` is already bound, nothing gets changed and the old value is returned.
+    pub fn insert(&mut self, sequence: &KeySequence<'_>, value: T) -> Option<&T> {
+        let node =
+            sequence
+                .iter()
+                .flat_map(Key::strokes)
+                .fold(NodeId::ROOT, |NodeId(node), stroke| {
+                    let len = self.0.len();
+                    let child = *self.0[node].children.entry(stroke).or_insert(NodeId(len));
+                    if child.0 == len {
+                        self.0.push(Node::default());
+                    }
+
+                    child
+                });
+
+        match &mut self.0[node.0].value {
+            Some(old) => Some(old),
+            slot @ None => {
+                *slot = Some(value);
+                None
+            }
+        }
+    }
+
+    /// Advance from `node` by the key presses in `key`.
+    ///
+    /// Modifiers of `key` that are on a specific side also match key sequences that allow either
+    /// side.
+    pub fn step(&self, NodeId(mut node): NodeId, key: &Key<'_>) -> KeyMatch<'_, T> {
+        for stroke in key.strokes() {
+            let children = &self.0[node].children;
+            match stroke
+                .generalizations()
+                .find_map(|stroke| children.get(&stroke))
+            {
+                Some(NodeId(child)) => node = *child,
+                None => return KeyMatch::None,
+            }
+
+            if let Some(value) = &self.0[node].value {
+                return KeyMatch::Complete(value);
+            }
+        }
+
+        KeyMatch::Prefix(NodeId(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::config::key::{KeyModifier, KeyModifiers, Parser},
+    };
+
+    fn sequence(input: &str) -> KeySequence<'_> {
+        KeySequence::parse(input).unwrap().unwrap().0
+    }
+
+    #[test]
+    fn key_trie_step() {
+        let mut trie = KeyTrie::default();
+        [("L-q", 0), ("C-xC-c", 1), ("C-xk", 2), ("foo", 3)]
+            .into_iter()
+            .for_each(|(input, value)| assert_eq!(trie.insert(&sequence(input), value), None));
+
+        let control = KeyModifiers::from_iter([KeyModifier::Control]);
+        let none = KeyModifiers::default();
+
+        assert_eq!(
+            trie.step(
+                NodeId::ROOT,
+                &Key::new(KeyModifiers::from_iter([KeyModifier::Super]), "q".into())
+            ),
+            KeyMatch::Complete(&0)
+        );
+        assert_eq!(
+            trie.step(NodeId::ROOT, &Key::new(none, "q".into())),
+            KeyMatch::None
+        );
+
+        let KeyMatch::Prefix(node) = trie.step(NodeId::ROOT, &Key::new(control, "x".into())) else {
+            panic!("`C-x` should be a prefix");
+        };
+        assert_eq!(
+            trie.step(node, &Key::new(control, "c".into())),
+            KeyMatch::Complete(&1)
+        );
+        // modifiers apply to every character after them
+        assert_eq!(
+            trie.step(node, &Key::new(control, "k".into())),
+            KeyMatch::Complete(&2)
+        );
+        assert_eq!(trie.step(node, &Key::new(none, "c".into())), KeyMatch::None);
+
+        // keys with multiple characters are the same as pressing them one by one
+        assert_eq!(
+            trie.step(NodeId::ROOT, &Key::new(none, "foo".into())),
+            KeyMatch::Complete(&3)
+        );
+        let node = ["f", "o"].into_iter().fold(NodeId::ROOT, |node, key| {
+            match trie.step(node, &Key::new(none, key.into())) {
+                KeyMatch::Prefix(node) => node,
+                _ => panic!("`{}` should be a prefix", key),
+            }
+        });
+        assert_eq!(
+            trie.step(node, &Key::new(none, "o".into())),
+            KeyMatch::Complete(&3)
+        );
+    }
+
+    #[test]
+    fn key_trie_sides() {
+        let mut trie = KeyTrie::default();
+        [("L-q", 0), ("Lr-w", 1), ("<TAP-L>", 2), ("<TAP-Ll>", 3)]
+            .into_iter()
+            .for_each(|(input, value)| assert_eq!(trie.insert(&sequence(input), value), None));
+
+        let step =
+            |input| match trie.step(NodeId::ROOT, &sequence(input).into_iter().next().unwrap()) {
+                KeyMatch::Complete(value) => Some(*value),
+                _ => None,
+            };
+        assert_eq!(step("Ll-q"), Some(0));
+        assert_eq!(step("Lr-q"), Some(0));
+        assert_eq!(step("Lr-w"), Some(1));
+        assert_eq!(step("Ll-w"), None);
+        assert_eq!(step("L-w"), None);
+        assert_eq!(step("<TAP-Lr>"), Some(2));
+        assert_eq!(step("<TAP-Ll>"), Some(3));
+    }
+
+    #[test]
+    fn key_trie_most_specific() {
+        let bindings = [
+            ("M-C-S-q", 0),
+            ("Ml-Cl-S-q", 1),
+            ("M-C-Sl-q", 2),
+            ("Ml-C-Sl-q", 3),
+        ];
+        let step = |bindings: &[(&str, usize)], input| {
+            let mut trie = KeyTrie::default();
+            bindings
+                .iter()
+                .for_each(|(input, value)| assert_eq!(trie.insert(&sequence(input), *value), None));
+
+            match trie.step(NodeId::ROOT, &sequence(input).into_iter().next().unwrap()) {
+                KeyMatch::Complete(value) => Some(*value),
+                _ => None,
+            }
+        };
+
+        // the binding that ignores the side of the fewest modifiers wins
+        assert_eq!(step(&bindings, "Ml-Cl-Sl-q"), Some(3));
+        assert_eq!(step(&bindings[..3], "Ml-Cl-Sl-q"), Some(1));
+        assert_eq!(step(&bindings[..1], "Ml-Cl-Sl-q"), Some(0));
+        assert_eq!(step(&bindings, "Mr-Cl-Sr-q"), Some(0));
+
+        // ties are decided the same way no matter which binding came first
+        let mut tied = [("Ml-C-q", 0), ("M-Cl-q", 1)];
+        assert_eq!(step(&tied, "Ml-Cl-q"), Some(1));
+        tied.reverse();
+        assert_eq!(step(&tied, "Ml-Cl-q"), Some(1));
+    }
+
+    #[test]
+    fn key_trie_duplicates() {
+        let mut trie = KeyTrie::default();
+
+        assert_eq!(trie.insert(&sequence("C-xC-c"), 0), None);
+        assert_eq!(trie.insert(&sequence("C-x"), 1), None);
+        assert_eq!(trie.insert(&sequence("C-xC-c"), 2), Some(&0));
+        assert_eq!(trie.insert(&sequence("C-x"), 3), Some(&1));
+    }
+}
@@ -0,0 +1,324 @@
+use {
+    crate::{
+        config::key::{Key, KeyAction, KeyKind, KeySequence, KeyStroke, trie::KeyTrie},
+        state::KeyIntercept,
+    },
+    smallvec::{SmallVec, smallvec},
+    std::ops::{Index, IndexMut},
+};
+
+#[derive(Debug, PartialEq)]
+/// A key sequence and the action it executes.
+pub struct KeyBinding {
+    pub action: KeyAction,
+    /// Whether the last key of [Self::sequence] reaches the focused window.
+    pub intercept: KeyIntercept,
+    pub sequence: KeySequence<'static>,
+    pub trigger: KeyTrigger,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// When the action of a [KeyBinding] gets executed while its last key is held down.
+pub enum KeyTrigger {
+    /// Once, when the key gets pressed.
+    #[default]
+    Press,
+    /// Once, when the key gets released.
+    Release,
+    /// When the key gets pressed and every time it repeats afterwards.
+    Repeat,
+}
+
+#[derive(Debug)]
+/// A problem with the key bindings of a mode, found by [Modes::build_key_tries].
+pub enum KeyConflict<'a> {
+    /// The same key sequence is bound more than once, so only the first binding is used.
+    Duplicate {
+        kept: &'a KeyBinding,
+        ignored: &'a KeyBinding,
+    },
+    /// A key sequence starts with another key sequence, which completes first so the longer one
+    /// can never be matched.
+    Prefix {
+        prefix: &'a KeyBinding,
+        unreachable: &'a KeyBinding,
+    },
+    /// Two different key sequences that can be matched by the same key presses, because one
+    /// allows either side of a modifier where the other does not, so those key presses only
+    /// match `used`, see [KeyTrie::step].
+    Overlap {
+        used: &'a KeyBinding,
+        shadowed: &'a KeyBinding,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Index of a [Mode] inside of [Modes].
+pub struct ModeId(usize);
+impl ModeId {
+    /// The mode that is active on startup, which always exists.
+    pub const DEFAULT: Self = Self(0);
+}
+
+#[derive(Debug)]
+/// A named group of key bindings, only one of which is active at a time.
+pub struct Mode {
+    pub name: String,
+    /// What happens to keyboard keys that are not part of any key binding while this mode is
+    /// active, see [Self::unmatched].
+    pub unmatched: KeyIntercept,
+    pub key_bindings: SmallVec<[KeyBinding; 4]>,
+    /// Indices into [Self::key_bindings], built by [Modes::build_key_tries].
+    pub key_trie: KeyTrie<usize>,
+}
+impl Mode {
+    pub const DEFAULT_NAME: &'static str = "default";
+
+    /// What happens to `key` if it is not part of any key binding, which always lets mouse keys
+    /// through so that the mouse stays usable in modes that block unmatched keys.
+    pub fn unmatched(&self, key: &Key<'_>) -> KeyIntercept {
+        match key.kind() {
+            KeyKind::Mouse(_) => KeyIntercept::Allow,
+            _ => self.unmatched,
+        }
+    }
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            unmatched: KeyIntercept::Allow,
+            key_bindings: SmallVec::new(),
+            key_trie: KeyTrie::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// All the modes that were declared, starting with [Mode::DEFAULT_NAME].
+pub struct Modes(SmallVec<[Mode; 2]>);
+impl Default for Modes {
+    fn default() -> Self {
+        Self(smallvec![Mode::new(Mode::DEFAULT_NAME)])
+    }
+}
+impl Modes {
+    pub fn find(&self, name: &str) -> Option<ModeId> {
+        self.0.iter().position(|mode| mode.name == name).map(ModeId)
+    }
+
+    /// Get the mode called `name`, creating it if it does not exist.
+    pub fn get_or_insert(&mut self, name: &str) -> ModeId {
+        self.find(name).unwrap_or_else(|| {
+            self.0.push(Mode::new(name));
+            ModeId(self.0.len() - 1)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Mode> {
+        self.0.iter()
+    }
+
+    /// Rebuild [Mode::key_trie] for every mode.
+    ///
+    /// `conflict` is called with the mode and the problem whenever key bindings get in each
+    /// other's way.
+    pub fn build_key_tries<F>(&mut self, mut conflict: F)
+    where
+        F: FnMut(&Mode, KeyConflict<'_>),
+    {
+        self.0.iter_mut().for_each(|mode| {
+            let mut trie = KeyTrie::default();
+            let strokes = mode
+                .key_bindings
+                .iter()
+                .map(|binding| {
+                    binding
+                        .sequence
+                        .iter()
+                        .flat_map(Key::strokes)
+                        .collect::<SmallVec<[KeyStroke; 8]>>()
+                })
+                .collect::<SmallVec<[_; 4]>>();
+            // whether each key binding made it into the trie
+            let mut inserted = SmallVec::<[bool; 4]>::from_elem(false, strokes.len());
+
+            mode.key_bindings
+                .iter()
+                .enumerate()
+                .for_each(|(i, binding)| {
+                    if let Some(&kept) = trie.insert(&binding.sequence, i) {
+                        conflict(
+                            mode,
+                            KeyConflict::Duplicate {
+                                kept: &mode.key_bindings[kept],
+                                ignored: binding,
+                            },
+                        );
+                    } else {
+                        inserted[i] = true;
+                    }
+                });
+
+            let inserted = |i: &usize| inserted[*i];
+            (0..strokes.len()).filter(inserted).for_each(|prefix| {
+                (0..strokes.len())
+                    .filter(inserted)
+                    .filter(|&longer| {
+                        strokes[longer].len() > strokes[prefix].len()
+                            && strokes[longer].starts_with(&strokes[prefix])
+                    })
+                    .for_each(|longer| {
+                        conflict(
+                            mode,
+                            KeyConflict::Prefix {
+                                prefix: &mode.key_bindings[prefix],
+                                unreachable: &mode.key_bindings[longer],
+                            },
+                        )
+                    });
+            });
+            (0..strokes.len()).filter(inserted).for_each(|first| {
+                (first + 1..strokes.len())
+                    .filter(inserted)
+                    .for_each(|second| {
+                        if let Some(first_used) = overlap(&strokes[first], &strokes[second]) {
+                            let (used, shadowed) = match first_used {
+                                true => (first, second),
+                                false => (second, first),
+                            };
+                            conflict(
+                                mode,
+                                KeyConflict::Overlap {
+                                    used: &mode.key_bindings[used],
+                                    shadowed: &mode.key_bindings[shadowed],
+                                },
+                            )
+                        }
+                    });
+            });
+
+            mode.key_trie = trie;
+        });
+    }
+}
+
+/// Whether some key presses match both `a` and `b`, which differ before either of them ends,
+/// returning whether [KeyTrie::step] matches `a` instead of `b` for them.
+fn overlap(a: &[KeyStroke], b: &[KeyStroke]) -> Option<bool> {
+    let (a_first, b_first) = a.iter().zip(b).find(|(a, b)| a != b)?;
+    a.iter()
+        .zip(b)
+        .try_for_each(|(a, b)| a.meet(*b).map(drop))?;
+
+    // the strokes before are the same, so this is where the key trie has to pick one of them
+    a_first
+        .meet(*b_first)?
+        .generalizations()
+        .find(|stroke| stroke == a_first || stroke == b_first)
+        .map(|stroke| stroke == *a_first)
+}
+
+impl Index<ModeId> for Modes {
+    type Output = Mode;
+
+    fn index(&self, ModeId(id): ModeId) -> &Mode {
+        &self.0[id]
+    }
+}
+impl IndexMut<ModeId> for Modes {
+    fn index_mut(&mut self, ModeId(id): ModeId) -> &mut Mode {
+        &mut self.0[id]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::config::key::Parser};
+
+    #[test]
+    fn modes_get_or_insert() {
+        let mut modes = Modes::default();
+
+        assert_eq!(modes.get_or_insert(Mode::DEFAULT_NAME), ModeId::DEFAULT);
+        let resize = modes.get_or_insert("resize");
+        assert_ne!(resize, ModeId::DEFAULT);
+        assert_eq!(modes.get_or_insert("resize"), resize);
+        assert_eq!(modes.find("resize"), Some(resize));
+        assert_eq!(modes.find("move"), None);
+        assert_eq!(modes[resize].name, "resize");
+        assert_eq!(modes.iter().count(), 2);
+    }
+
+    #[test]
+    fn mode_unmatched() {
+        let mut mode = Mode::new("resize");
+        mode.unmatched = KeyIntercept::Block;
+        let key = |input| {
+            KeySequence::parse(input)
+                .unwrap()
+                .unwrap()
+                .0
+                .into_iter()
+                .next()
+                .unwrap()
+        };
+
+        assert_eq!(mode.unmatched(&key("a")), KeyIntercept::Block);
+        assert_eq!(mode.unmatched(&key("<F-1>")), KeyIntercept::Block);
+        assert_eq!(mode.unmatched(&key("<MB-1>")), KeyIntercept::Allow);
+        assert_eq!(mode.unmatched(&key("C-<WHEEL-UP>")), KeyIntercept::Allow);
+    }
+
+    #[test]
+    fn key_conflicts() {
+        let mut modes = Modes::default();
+        [
+            "C-xC-c", "C-x", "C-xC-c", "q", "C-xC-k", "L-a", "Ll-ab", "Lr-a",
+        ]
+        .into_iter()
+        .for_each(|input| {
+            modes[ModeId::DEFAULT].key_bindings.push(KeyBinding {
+                action: KeyAction::Quit,
+                intercept: KeyIntercept::Block,
+                sequence: KeySequence::parse(input).unwrap().unwrap().0.into_owned(),
+                trigger: KeyTrigger::Press,
+            })
+        });
+
+        let mut conflicts = Vec::new();
+        modes.build_key_tries(|mode, conflict| {
+            assert_eq!(mode.name, Mode::DEFAULT_NAME);
+            conflicts.push(match conflict {
+                KeyConflict::Duplicate { kept, ignored } => (
+                    "duplicate",
+                    kept.sequence.to_string(),
+                    ignored.sequence.to_string(),
+                ),
+                KeyConflict::Prefix {
+                    prefix,
+                    unreachable,
+                } => (
+                    "prefix",
+                    prefix.sequence.to_string(),
+                    unreachable.sequence.to_string(),
+                ),
+                KeyConflict::Overlap { used, shadowed } => (
+                    "overlap",
+                    used.sequence.to_string(),
+                    shadowed.sequence.to_string(),
+                ),
+            })
+        });
+
+        assert_eq!(
+            conflicts,
+            [
+                ("duplicate", "C-xC-c".into(), "C-xC-c".into()),
+                ("prefix", "C-x".into(), "C-xC-c".into()),
+                ("prefix", "C-x".into(), "C-xC-k".into()),
+                ("overlap", "Ll-aLl-b".into(), "L-a".into()),
+                ("overlap", "Lr-a".into(), "L-a".into()),
+            ]
+        );
+    }
+}
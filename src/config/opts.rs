@@ -86,6 +86,12 @@ pub enum ArgError {
     Separator,
     Value,
 }
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgvError<'a, E> {
+    /// An argument that is neither a flag nor the value of one.
+    Positional(&'a str),
+    Source(E),
+}
 
 pub struct Argv<'a, I, E>
 where
@@ -94,6 +100,8 @@ where
     iter: I,
     last: Option<Arg<'a>>,
     passed_separator: bool,
+    /// How many arguments were taken from [Self::iter].
+    read: usize,
     _marker: PhantomData<E>,
 }
 impl<'a, I, O, E> From<I> for Argv<'a, O, E>
@@ -106,6 +114,7 @@ where
             iter: iter.into_iter(),
             last: None,
             passed_separator: false,
+            read: 0,
             _marker: PhantomData,
         }
     }
@@ -114,13 +123,20 @@ impl<'a, I, E> Argv<'a, I, E>
 where
     I: Iterator<Item = Result<&'a str, E>>,
 {
+    fn next_arg(&mut self) -> Option<Result<&'a str, E>> {
+        let arg = self.iter.next()?;
+        self.read += 1;
+        Some(arg)
+    }
+
     /// Returns none if there are no more arguments.
     fn last_or_next(&mut self) -> Option<Result<&mut Arg<'a>, E>> {
         if self.last.is_none() {
-            Some(Ok(self.last.insert(match self.iter.next()? {
+            let arg = match self.next_arg()? {
                 Ok(arg) => Arg::from(arg),
                 Err(err) => return Some(Err(err)),
-            })))
+            };
+            Some(Ok(self.last.insert(arg)))
         } else {
             self.last.as_mut().map(Ok)
         }
@@ -129,7 +145,7 @@ where
     /// Get a value if it exists.
     pub fn value(&mut self) -> Option<Result<&'a str, E>> {
         self.last.take().and_then(Arg::value).map(Ok).or_else(|| {
-            let value = match self.iter.next()? {
+            let value = match self.next_arg()? {
                 Ok(value) => value,
                 Err(err) => return Some(Err(err)),
             };
@@ -142,14 +158,30 @@ where
             }
         })
     }
+
+    /// How many arguments have been read, including the one that the last flag or value is from.
+    pub const fn args_read(&self) -> usize {
+        self.read
+    }
+
+    /// Drop what is left of the current argument, such as the short flags that were grouped with
+    /// a flag that could not be applied.
+    pub fn skip_arg(&mut self) {
+        self.last = None;
+    }
+
+    /// Get the arguments after the `--` separator, returns none if it was not reached.
+    pub fn remainder(self) -> Option<I> {
+        self.passed_separator.then_some(self.iter)
+    }
 }
 impl<'a, I, E> Iterator for Argv<'a, I, E>
 where
     I: Iterator<Item = Result<&'a str, E>>,
 {
-    type Item = Result<Flag<'a>, E>;
+    type Item = Result<Flag<'a>, ArgvError<'a, E>>;
 
-    fn next(&mut self) -> Option<Result<Flag<'a>, E>> {
+    fn next(&mut self) -> Option<Result<Flag<'a>, ArgvError<'a, E>>> {
         Recursion::start(self, |s| {
             if s.passed_separator {
                 return Recursion::End(None);
@@ -157,16 +189,21 @@ where
 
             let arg = match s.last_or_next() {
                 Some(Ok(arg)) => arg,
-                Some(Err(err)) => return Recursion::End(Some(Err(err))),
+                Some(Err(err)) => return Recursion::End(Some(Err(ArgvError::Source(err)))),
                 None => return Recursion::End(None),
             };
 
             match arg.next().transpose() {
                 Ok(flag @ Some(_)) => Recursion::End(flag.map(Ok)),
-                Ok(None) | Err(ArgError::Value) => {
+                Ok(None) => {
                     s.last = None;
                     Recursion::Continue(s)
                 }
+                Err(ArgError::Value) => {
+                    let positional = arg.next;
+                    s.last = None;
+                    Recursion::End(Some(Err(ArgvError::Positional(positional))))
+                }
                 Err(ArgError::Separator) => {
                     s.passed_separator = true;
                     Recursion::End(None)
@@ -326,24 +363,65 @@ mod tests {
                 &["--foo", "bar", "-lsh"] as &[_],
                 1,
                 "bar",
+                2,
                 &[Flag::Short('l'), Flag::Short('s'), Flag::Short('h')] as &[_],
             ),
             (
                 &["--foo=bar", "-lsh"] as &[_],
                 1,
                 "bar",
+                1,
                 &[Flag::Short('l'), Flag::Short('s'), Flag::Short('h')],
             ),
-            (&["--foo=bar", "-lsh"] as &[_], 2, "sh", &[]),
+            (&["--foo=bar", "-lsh"] as &[_], 2, "sh", 2, &[]),
         ]
         .into_iter()
-        .for_each(|(input, nth, expected_value, expected_flags)| {
+        .for_each(|(input, nth, expected_value, read, expected_flags)| {
             let mut argv = Argv::from(input.iter().copied().map(Ok::<_, Infallible>));
             (0..nth).map(|_| argv.next()).for_each(drop);
             assert_eq!(argv.value(), Some(Ok(expected_value)));
+            assert_eq!(argv.args_read(), read);
 
             argv.enumerate()
                 .for_each(|(i, flag)| assert_eq!(flag, Ok(expected_flags[i])));
         })
     }
+    #[test]
+    fn argv_positional() {
+        let mut argv = Argv::from(
+            ["-afoo", "bar", "-", "-l"]
+                .into_iter()
+                .map(Ok::<_, Infallible>),
+        );
+
+        assert_eq!(argv.next(), Some(Ok(Flag::Short('a'))));
+        assert_eq!(argv.value(), Some(Ok("foo")));
+        assert_eq!(argv.next(), Some(Err(ArgvError::Positional("bar"))));
+        assert_eq!(argv.args_read(), 2);
+        assert_eq!(argv.next(), Some(Err(ArgvError::Positional("-"))));
+        assert_eq!(argv.next(), Some(Ok(Flag::Short('l'))));
+        assert_eq!(argv.next(), None);
+    }
+    #[test]
+    fn argv_remainder() {
+        [
+            (
+                &["--foo", "--", "bar", "-baz"] as &[_],
+                Some(&["bar", "-baz"] as &[_]),
+            ),
+            (&["--foo", "--"], Some(&[])),
+            (&["--foo", "bar"], None),
+        ]
+        .into_iter()
+        .for_each(|(input, expected)| {
+            let mut argv = Argv::from(input.iter().copied().map(Ok::<_, Infallible>));
+            argv.by_ref().for_each(drop);
+
+            assert_eq!(
+                argv.remainder()
+                    .map(|rest| rest.collect::<Result<Vec<_>, _>>().unwrap()),
+                expected.map(Vec::from),
+            );
+        })
+    }
 }
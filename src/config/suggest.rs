@@ -0,0 +1,148 @@
+use {
+    smallvec::SmallVec,
+    std::{
+        fmt::{self, Display, Formatter},
+        mem,
+    },
+};
+
+/// The most candidates that get suggested at once.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The number of single character insertions, deletions, substitutions and swaps of adjacent
+/// characters needed to turn `lhs` into `rhs`, ignoring ascii case.
+pub fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let lhs = lhs.chars().collect::<SmallVec<[char; 32]>>();
+    let rhs = rhs.chars().collect::<SmallVec<[char; 32]>>();
+    let eq = |i: usize, j: usize| lhs[i].eq_ignore_ascii_case(&rhs[j]);
+
+    // the rows for the previous two characters of `lhs` and the current one
+    let mut before = SmallVec::<[usize; 32]>::from_elem(0, rhs.len() + 1);
+    let mut previous = (0..=rhs.len()).collect::<SmallVec<[usize; 32]>>();
+    let mut current = SmallVec::<[usize; 32]>::from_elem(0, rhs.len() + 1);
+
+    (0..lhs.len()).for_each(|i| {
+        current[0] = i + 1;
+        (0..rhs.len()).for_each(|j| {
+            current[j + 1] = (previous[j] + usize::from(!eq(i, j)))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+            if i > 0 && j > 0 && eq(i, j - 1) && eq(i - 1, j) {
+                current[j + 1] = current[j + 1].min(before[j - 1] + 1);
+            }
+        });
+
+        mem::swap(&mut before, &mut previous);
+        mem::swap(&mut previous, &mut current);
+    });
+
+    previous[rhs.len()]
+}
+
+#[derive(Debug, PartialEq)]
+/// The candidates closest to some input, which display as `, did you mean ...?` or nothing if
+/// none of them are close enough to be a likely typo.
+pub struct Suggestions<S>(SmallVec<[S; MAX_SUGGESTIONS]>);
+impl<S> Suggestions<S>
+where
+    S: AsRef<str>,
+{
+    pub fn new<I>(input: &str, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+    {
+        // allow about one typo for every three characters
+        let max_distance = (input.chars().count() / 3).max(1);
+        let mut closest = candidates
+            .into_iter()
+            .map(|candidate| (edit_distance(input, candidate.as_ref()), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect::<Vec<_>>();
+        let Some(min) = closest.iter().map(|(distance, _)| *distance).min() else {
+            return Self(SmallVec::new());
+        };
+        closest.retain(|(distance, _)| *distance == min);
+        closest.sort_by(|(_, lhs), (_, rhs)| lhs.as_ref().cmp(rhs.as_ref()));
+        closest.dedup_by(|(_, lhs), (_, rhs)| lhs.as_ref() == rhs.as_ref());
+
+        Self(
+            closest
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|(_, candidate)| candidate)
+                .collect(),
+        )
+    }
+}
+impl<S> Display for Suggestions<S>
+where
+    S: AsRef<str>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some((last, rest)) = self.0.split_last() else {
+            return Ok(());
+        };
+
+        write!(f, ", did you mean ")?;
+        rest.iter().enumerate().try_for_each(|(i, candidate)| {
+            write!(
+                f,
+                "`{}`{}",
+                candidate.as_ref(),
+                if i + 1 == rest.len() { " or " } else { ", " }
+            )
+        })?;
+        write!(f, "`{}`?", last.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_cases() {
+        [
+            ("", "", 0),
+            ("", "abc", 3),
+            ("abc", "", 3),
+            ("kitten", "sitting", 3),
+            ("quit", "QUIT", 0),
+            ("hepl", "help", 1),
+            ("qiut", "quit", 1),
+            ("ab", "bca", 3),
+            ("verbos", "verbose", 1),
+        ]
+        .into_iter()
+        .for_each(|(lhs, rhs, distance)| {
+            assert_eq!(edit_distance(lhs, rhs), distance);
+            assert_eq!(edit_distance(rhs, lhs), distance);
+        });
+    }
+
+    #[test]
+    fn suggestions() {
+        [
+            (
+                "verbos",
+                &["none", "quiet", "verbose"] as &[_],
+                ", did you mean `verbose`?",
+            ),
+            ("foo", &["none", "quiet", "verbose"], ""),
+            (
+                "cat",
+                &["bat", "car", "hat", "cut"],
+                ", did you mean `bat`, `car` or `cut`?",
+            ),
+            ("ba", &["bat", "bar"], ", did you mean `bar` or `bat`?"),
+            ("x", &[], ""),
+        ]
+        .into_iter()
+        .for_each(|(input, candidates, output)| {
+            assert_eq!(
+                Suggestions::new(input, candidates.iter().copied()).to_string(),
+                output
+            );
+        });
+    }
+}
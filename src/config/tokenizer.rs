@@ -0,0 +1,144 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenizerError {
+    /// A quote that was not closed before the end of the line.
+    UnclosedQuote(char),
+}
+impl Display for TokenizerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedQuote(quote) => write!(f, "unclosed quote `{}`", quote),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Splits a line of a config file into arguments the way a shell would.
+///
+/// Arguments are separated by whitespace, and a `#` at the start of an argument comments out the
+/// rest of the line. Text inside `'` is taken as is, while inside `"` a `\` can escape `"` and `\`.
+/// Outside of quotes, `\` escapes whitespace, quotes, `\` and `#`, and is kept before anything
+/// else so that windows paths do not need quoting.
+///
+/// Arguments without quotes or escapes are borrowed from the line.
+pub struct Tokenizer<'a> {
+    input: &'a str,
+}
+impl<'a> Tokenizer<'a> {
+    pub const fn new(input: &'a str) -> Self {
+        Self { input }
+    }
+}
+impl<'a> From<&'a str> for Tokenizer<'a> {
+    fn from(input: &'a str) -> Self {
+        Self::new(input)
+    }
+}
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Cow<'a, str>, TokenizerError>;
+
+    fn next(&mut self) -> Option<Result<Cow<'a, str>, TokenizerError>> {
+        let input = self.input.trim_start();
+        if input.is_empty() || input.starts_with('#') {
+            self.input = "";
+            return None;
+        }
+
+        // only allocated once the argument stops being a slice of the input
+        let mut owned: Option<String> = None;
+        let mut end = input.len();
+        let mut chars = input.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                ch if ch.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                '\'' | '"' => {
+                    let token = owned.get_or_insert_with(|| input[..i].to_owned());
+                    loop {
+                        match chars.next() {
+                            Some((_, close)) if close == ch => break,
+                            Some((_, '\\')) if ch == '"' => {
+                                match chars.next_if(|(_, next)| matches!(next, '"' | '\\')) {
+                                    Some((_, escaped)) => token.push(escaped),
+                                    None => token.push('\\'),
+                                }
+                            }
+                            Some((_, ch)) => token.push(ch),
+                            None => {
+                                self.input = "";
+                                return Some(Err(TokenizerError::UnclosedQuote(ch)));
+                            }
+                        }
+                    }
+                }
+                '\\' => {
+                    let token = owned.get_or_insert_with(|| input[..i].to_owned());
+                    match chars.next_if(|(_, next)| {
+                        next.is_whitespace() || matches!(next, '\'' | '"' | '\\' | '#')
+                    }) {
+                        Some((_, escaped)) => token.push(escaped),
+                        None => token.push('\\'),
+                    }
+                }
+                ch => {
+                    if let Some(token) = &mut owned {
+                        token.push(ch);
+                    }
+                }
+            }
+        }
+        self.input = &input[end..];
+
+        Some(Ok(match owned {
+            Some(token) => Cow::Owned(token),
+            None => Cow::Borrowed(&input[..end]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizer_iter() {
+        [
+            ("-Kquit -kL-S-Q", Ok(&["-Kquit", "-kL-S-Q"] as &[_])),
+            ("  --help\t--version  ", Ok(&["--help", "--version"])),
+            ("-a'foo  bar' baz", Ok(&["-afoo  bar", "baz"])),
+            (r#"-a"say \"hi\" \n" x"#, Ok(&[r#"-asay "hi" \n"#, "x"])),
+            (r#"-a foo\ bar\#\\"#, Ok(&["-a", r#"foo bar#\"#])),
+            (
+                r"-cC:\Users\storm\config.txt",
+                Ok(&[r"-cC:\Users\storm\config.txt"]),
+            ),
+            ("-k# -kq # comment", Ok(&["-k#", "-kq"])),
+            ("'' -w", Ok(&["", "-w"])),
+            ("# comment", Ok(&[])),
+            ("-a'foo", Err(TokenizerError::UnclosedQuote('\''))),
+            (r#"-a"foo\""#, Err(TokenizerError::UnclosedQuote('"'))),
+        ]
+        .into_iter()
+        .for_each(|(input, output)| {
+            assert_eq!(
+                Tokenizer::new(input).collect::<Result<Vec<_>, _>>(),
+                output.map(|output| output.iter().copied().map(Cow::Borrowed).collect()),
+                "{}",
+                input
+            );
+        });
+
+        assert!(
+            Tokenizer::new("-Kquit 'q'")
+                .map(Result::unwrap)
+                .map(|token| matches!(token, Cow::Borrowed(_)))
+                .eq([true, false])
+        );
+    }
+}
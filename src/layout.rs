@@ -0,0 +1,427 @@
+//! Where windows get placed on the screen.
+
+use {crate::backend::Rect, std::iter};
+
+/// The smallest share of the screen width that either side of the split can be resized to.
+const MIN_RATIO: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Puts the first window on the left of the screen and stacks the others on the right.
+pub struct Layout {
+    /// How much of the screen width the first window takes up.
+    ratio: f32,
+}
+impl Default for Layout {
+    fn default() -> Self {
+        Self { ratio: 0.5 }
+    }
+}
+impl Layout {
+    pub const fn ratio(&self) -> f32 {
+        self.ratio
+    }
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+    }
+    /// Move the split between the first window and the others to `x`, a position on the screen.
+    pub fn split_at(&mut self, area: Rect, x: i16) {
+        if area.width() != 0 {
+            self.set_ratio((i32::from(x) - i32::from(area.x())) as f32 / f32::from(area.width()));
+        }
+    }
+
+    /// The position of each of `count` windows inside of `area`.
+    pub fn tile(&self, area: Rect, count: usize) -> impl Iterator<Item = Rect> {
+        let stacked = count.saturating_sub(1);
+        let width = match stacked {
+            0 => area.width(),
+            _ => (f32::from(area.width()) * self.ratio) as u16,
+        };
+        let height = u32::from(area.height());
+        // the edges are computed from the top so the remainder is spread out between windows
+        let edge = move |i: usize| (height * i as u32 / stacked as u32) as i32;
+
+        iter::once(Rect::new(area.x(), area.y(), width, area.height()))
+            .chain((0..stacked).map(move |i| {
+                from_edges(
+                    i32::from(area.x()) + i32::from(width),
+                    i32::from(area.y()) + edge(i),
+                    i32::from(area.x()) + i32::from(area.width()),
+                    i32::from(area.y()) + edge(i + 1),
+                )
+            }))
+            .take(count)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl Corner {
+    /// The corner of `rect` that is closest to `(x, y)`.
+    pub fn nearest(rect: Rect, (x, y): (i16, i16)) -> Self {
+        let left = i32::from(x) < i32::from(rect.x()) + i32::from(rect.width()) / 2;
+        let top = i32::from(y) < i32::from(rect.y()) + i32::from(rect.height()) / 2;
+
+        match (left, top) {
+            (true, true) => Self::TopLeft,
+            (false, true) => Self::TopRight,
+            (true, false) => Self::BottomLeft,
+            (false, false) => Self::BottomRight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+impl Direction {
+    /// Every name accepted by [Self::from_name].
+    pub const NAMES: [&str; 4] = ["left", "right", "up", "down"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            _ => None,
+        }
+    }
+
+    /// How far moving `distance` pixels towards [self] goes on each axis.
+    const fn delta(&self, distance: i32) -> (i32, i32) {
+        match self {
+            Self::Left => (-distance, 0),
+            Self::Right => (distance, 0),
+            Self::Up => (0, -distance),
+            Self::Down => (0, distance),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A half or a quarter of the screen.
+pub enum Region {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl Region {
+    /// Every name accepted by [Self::from_name].
+    pub const NAMES: [&str; 8] = [
+        "left",
+        "right",
+        "top",
+        "bottom",
+        "top-left",
+        "top-right",
+        "bottom-left",
+        "bottom-right",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "top-left" => Some(Self::TopLeft),
+            "top-right" => Some(Self::TopRight),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom-right" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+
+    /// The part of `area` that [self] covers.
+    pub fn of(&self, area: Rect) -> Rect {
+        let (left, top) = (i32::from(area.x()), i32::from(area.y()));
+        let (right, bottom) = (
+            left + i32::from(area.width()),
+            top + i32::from(area.height()),
+        );
+        let (center_x, center_y) = ((left + right) / 2, (top + bottom) / 2);
+
+        match self {
+            Self::Left => from_edges(left, top, center_x, bottom),
+            Self::Right => from_edges(center_x, top, right, bottom),
+            Self::Top => from_edges(left, top, right, center_y),
+            Self::Bottom => from_edges(left, center_y, right, bottom),
+            Self::TopLeft => from_edges(left, top, center_x, center_y),
+            Self::TopRight => from_edges(center_x, top, right, center_y),
+            Self::BottomLeft => from_edges(left, center_y, center_x, bottom),
+            Self::BottomRight => from_edges(center_x, center_y, right, bottom),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A way of moving or resizing a floating window from the keyboard.
+pub enum Placement {
+    /// Center the window on the screen.
+    Center,
+    /// Move an edge of the window outwards by a step.
+    Grow(Direction),
+    /// Move the window by a step.
+    Nudge(Direction),
+    /// Move an edge of the window inwards by a step.
+    Shrink(Direction),
+    /// Fit the window into a part of the screen.
+    Snap(Region),
+}
+impl Placement {
+    /// Parse `center` or one of the other placements followed by a colon and its argument, such
+    /// as `nudge:left`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once(':') {
+            None if name == "center" => Some(Self::Center),
+            None => None,
+            Some(("grow", edge)) => Direction::from_name(edge).map(Self::Grow),
+            Some(("nudge", direction)) => Direction::from_name(direction).map(Self::Nudge),
+            Some(("shrink", edge)) => Direction::from_name(edge).map(Self::Shrink),
+            Some(("snap", region)) => Region::from_name(region).map(Self::Snap),
+            Some(_) => None,
+        }
+    }
+
+    /// Every name accepted by [Self::from_name].
+    pub fn names() -> impl Iterator<Item = String> {
+        ["grow", "nudge", "shrink"]
+            .into_iter()
+            .flat_map(|placement| {
+                Direction::NAMES
+                    .into_iter()
+                    .map(move |direction| format!("{}:{}", placement, direction))
+            })
+            .chain(
+                Region::NAMES
+                    .into_iter()
+                    .map(|region| format!("snap:{}", region)),
+            )
+            .chain(["center".to_owned()])
+    }
+
+    /// Where a window at `rect` goes, where `area` is the screen and `step` is how many pixels it
+    /// moves or resizes by.
+    pub fn apply(&self, rect: Rect, area: Rect, step: u16) -> Rect {
+        let step = i32::from(step);
+
+        match self {
+            Self::Center => Rect::new(
+                clamp_position(
+                    i32::from(area.x()) + (i32::from(area.width()) - i32::from(rect.width())) / 2,
+                ),
+                clamp_position(
+                    i32::from(area.y()) + (i32::from(area.height()) - i32::from(rect.height())) / 2,
+                ),
+                rect.width(),
+                rect.height(),
+            ),
+            Self::Grow(edge) => resize_edge(rect, *edge, step),
+            Self::Nudge(direction) => translate(rect, direction.delta(step)),
+            Self::Shrink(edge) => resize_edge(rect, *edge, -step),
+            Self::Snap(region) => region.of(area),
+        }
+    }
+}
+
+fn clamp_position(position: i32) -> i16 {
+    position.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+}
+
+/// Create a [Rect] from the positions of its edges, clamped to what fits into a [Rect].
+fn from_edges(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+    let (left, top) = (clamp_position(left), clamp_position(top));
+
+    Rect::new(
+        left,
+        top,
+        (right - i32::from(left)).clamp(0, u16::MAX.into()) as u16,
+        (bottom - i32::from(top)).clamp(0, u16::MAX.into()) as u16,
+    )
+}
+
+/// Move `rect` by `(dx, dy)`.
+pub fn translate(rect: Rect, (dx, dy): (i32, i32)) -> Rect {
+    let translate = |position: i16, by: i32| clamp_position(i32::from(position).saturating_add(by));
+
+    Rect::new(
+        translate(rect.x(), dx),
+        translate(rect.y(), dy),
+        rect.width(),
+        rect.height(),
+    )
+}
+
+/// Drag `corner` of `rect` by `(dx, dy)`, keeping the opposite corner in place.
+///
+/// The dragged edges stop before they reach the opposite ones, so [Rect]s never flip.
+pub fn resize(rect: Rect, corner: Corner, (dx, dy): (i32, i32)) -> Rect {
+    let (mut left, mut top) = (i32::from(rect.x()), i32::from(rect.y()));
+    let (mut right, mut bottom) = (
+        left + i32::from(rect.width()),
+        top + i32::from(rect.height()),
+    );
+
+    match corner {
+        Corner::TopLeft | Corner::BottomLeft => left = left.saturating_add(dx).min(right - 1),
+        Corner::TopRight | Corner::BottomRight => right = right.saturating_add(dx).max(left + 1),
+    }
+    match corner {
+        Corner::TopLeft | Corner::TopRight => top = top.saturating_add(dy).min(bottom - 1),
+        Corner::BottomLeft | Corner::BottomRight => bottom = bottom.saturating_add(dy).max(top + 1),
+    }
+
+    from_edges(left, top, right, bottom)
+}
+
+/// Move `edge` of `rect` outwards by `distance`, or inwards if it is negative.
+fn resize_edge(rect: Rect, edge: Direction, distance: i32) -> Rect {
+    // the delta of the other edge that the corner moves is always 0
+    let corner = match edge {
+        Direction::Left | Direction::Up => Corner::TopLeft,
+        Direction::Right | Direction::Down => Corner::BottomRight,
+    };
+
+    resize(rect, corner, edge.delta(distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_tile() {
+        let area = Rect::new(0, 0, 100, 90);
+        let layout = Layout::default();
+
+        assert_eq!(layout.tile(area, 0).next(), None);
+        assert_eq!(layout.tile(area, 1).collect::<Vec<_>>(), [area]);
+        assert_eq!(
+            layout.tile(area, 4).collect::<Vec<_>>(),
+            [
+                Rect::new(0, 0, 50, 90),
+                Rect::new(50, 0, 50, 30),
+                Rect::new(50, 30, 50, 30),
+                Rect::new(50, 60, 50, 30),
+            ]
+        );
+
+        let mut layout = layout;
+        layout.split_at(area, 75);
+        assert_eq!(layout.ratio(), 0.75);
+        layout.split_at(area, 100);
+        assert_eq!(layout.ratio(), 1.0 - MIN_RATIO);
+        assert_eq!(
+            layout.tile(area, 2).collect::<Vec<_>>(),
+            [Rect::new(0, 0, 90, 90), Rect::new(90, 0, 10, 90)]
+        );
+    }
+
+    #[test]
+    fn corner_nearest() {
+        let rect = Rect::new(10, 10, 20, 20);
+
+        assert_eq!(Corner::nearest(rect, (11, 12)), Corner::TopLeft);
+        assert_eq!(Corner::nearest(rect, (29, 12)), Corner::TopRight);
+        assert_eq!(Corner::nearest(rect, (11, 29)), Corner::BottomLeft);
+        assert_eq!(Corner::nearest(rect, (20, 20)), Corner::BottomRight);
+    }
+
+    #[test]
+    fn rect_move_resize() {
+        let rect = Rect::new(10, 10, 20, 20);
+
+        assert_eq!(translate(rect, (-5, 5)), Rect::new(5, 15, 20, 20));
+        assert_eq!(
+            translate(rect, (i32::MAX, 0)),
+            Rect::new(i16::MAX, 10, 20, 20)
+        );
+
+        assert_eq!(
+            resize(rect, Corner::BottomRight, (5, -5)),
+            Rect::new(10, 10, 25, 15)
+        );
+        assert_eq!(
+            resize(rect, Corner::TopLeft, (5, -5)),
+            Rect::new(15, 5, 15, 25)
+        );
+        assert_eq!(
+            resize(rect, Corner::TopRight, (-50, 50)),
+            Rect::new(10, 29, 1, 1)
+        );
+    }
+
+    #[test]
+    fn placement() {
+        let area = Rect::new(0, 0, 100, 80);
+        let rect = Rect::new(10, 10, 20, 20);
+
+        assert_eq!(Placement::from_name("center"), Some(Placement::Center));
+        assert_eq!(
+            Placement::from_name("nudge:left"),
+            Some(Placement::Nudge(Direction::Left))
+        );
+        assert_eq!(
+            Placement::from_name("snap:bottom-right"),
+            Some(Placement::Snap(Region::BottomRight))
+        );
+        [
+            "centre",
+            "center:left",
+            "nudge",
+            "nudge:",
+            "grow:top",
+            "snap:up",
+            "move:left",
+        ]
+        .into_iter()
+        .for_each(|name| assert_eq!(Placement::from_name(name), None, "{}", name));
+        assert_eq!(Placement::names().count(), 21);
+        Placement::names()
+            .for_each(|name| assert!(Placement::from_name(&name).is_some(), "{}", name));
+
+        [
+            (Placement::Center, Rect::new(40, 30, 20, 20)),
+            (Placement::Nudge(Direction::Up), Rect::new(10, 5, 20, 20)),
+            (
+                Placement::Nudge(Direction::Right),
+                Rect::new(15, 10, 20, 20),
+            ),
+            (Placement::Grow(Direction::Left), Rect::new(5, 10, 25, 20)),
+            (Placement::Grow(Direction::Down), Rect::new(10, 10, 20, 25)),
+            (
+                Placement::Shrink(Direction::Right),
+                Rect::new(10, 10, 15, 20),
+            ),
+            (Placement::Shrink(Direction::Up), Rect::new(10, 15, 20, 15)),
+            (Placement::Snap(Region::Left), Rect::new(0, 0, 50, 80)),
+            (Placement::Snap(Region::Bottom), Rect::new(0, 40, 100, 40)),
+            (Placement::Snap(Region::TopRight), Rect::new(50, 0, 50, 40)),
+        ]
+        .into_iter()
+        .for_each(|(placement, expected)| {
+            assert_eq!(placement.apply(rect, area, 5), expected, "{:?}", placement)
+        });
+
+        // windows never get smaller than a pixel
+        assert_eq!(
+            Placement::Shrink(Direction::Left).apply(rect, area, 50),
+            Rect::new(29, 10, 1, 20)
+        );
+    }
+}
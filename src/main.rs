@@ -1,28 +1,26 @@
 #![cfg_attr(not(test), no_main)]
 
+mod arena;
 mod backend;
 mod bomb;
 mod config;
 mod const_string;
 mod error;
 mod iter_ext;
+mod layout;
 mod path_cache;
 mod recursion;
 mod state;
+mod supervisor;
+mod watcher;
 
 use {
-    config::{
-        ApplyError, Config,
-        file_parser::{self, FileParser},
-    },
+    config::{ApplyError, Config},
     either::Either,
     path_cache::PathCache,
-    path_cache::PathOrigin,
     std::{
-        convert::Infallible,
         env,
         ffi::{c_char, c_int},
-        fs::read_to_string,
     },
 };
 
@@ -33,44 +31,39 @@ pub const VERSION: &str = "0.1.0";
 // SAFETY: every c program has done this since the dawn of time
 #[cfg_attr(not(test), unsafe(no_mangle))]
 fn main(argc: c_int, argv: *const *const c_char) -> c_int {
-    let paths = PathCache::new();
-
     let mut config = Config::default();
-    match unsafe { config.apply_argv(&paths, argc, argv) } {
-        Ok(_) => {}
-        Err(Either::Right(ApplyError::Exit)) => return 0,
-        Err(err) => {
-            eprintln!("error during argument parsing: {}", err);
-            return 1;
-        }
-    }
+    // the config files and anything that errors borrow are freed once parsing is done
+    {
+        let paths = PathCache::new();
 
-    if let Some((path, origin)) = paths.get_config(&config) {
-        'apply: {
-            let contents = match read_to_string(path).map_err(move |err| (err, origin)) {
-                Ok(contents) => Box::leak(file_parser::trim_string(contents)),
-                Err((err, PathOrigin::Config)) => {
-                    config.error(|f| {
-                        writeln!(
-                            f,
-                            "failed to read configuration from path `{}`: {}",
-                            path.display(),
-                            err
-                        )
-                    });
-                    return 1;
-                }
-                Err((_, PathOrigin::Default)) => break 'apply, // ignore default
-            };
+        match unsafe { config.apply_argv(&paths, argc, argv) } {
+            Ok(_) => {}
+            Err(Either::Right(ApplyError::Exit)) => return 0,
+            Err(err) => {
+                eprintln!("error during argument parsing: {}", err);
+                return 1;
+            }
+        }
 
-            if let Err(err) =
-                config.apply_args(&paths, FileParser::new(contents).map(Ok::<_, Infallible>))
+        if let Err(errors) = config.apply_config_files(&paths) {
+            errors
+                .iter()
+                .for_each(|err| config.error(|f| writeln!(f, "{}", err)));
+            // leaving out a whole file could leave the window manager without a way to quit
+            if errors
+                .iter()
+                .any(|err| matches!(err, ApplyError::FileOpen(..)))
             {
-                config.error(|f| writeln!(f, "error during argument parsing: {}", err));
+                return 1;
             }
         }
     }
 
+    if let Err(err) = config.build_key_tries() {
+        config.error(|f| writeln!(f, "{}", err));
+        return 1;
+    }
+
     if cfg!(not(windows)) {
         config.error(|f| writeln!(f, "operating system `{}` is not supported", env::consts::OS));
         return 1;
@@ -78,14 +71,14 @@ fn main(argc: c_int, argv: *const *const c_char) -> c_int {
 
     #[cfg(windows)]
     {
-        state::Storm::<
+        let mut storm = state::Storm::<
             backend::windows::WindowsBackendState,
             backend::windows::WindowsWindow,
             backend::windows::WindowsBackendError,
         >::new(config)
-        .unwrap()
-        .run()
         .unwrap();
+        storm.autostart();
+        storm.run().unwrap();
     }
 
     0
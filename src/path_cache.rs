@@ -1,14 +1,21 @@
 use {
-    crate::{NAME, config::Config},
+    crate::{NAME, arena::Arena, config::Config},
     directories::BaseDirs,
     std::{
         cell::LazyCell,
+        fs, io,
         path::{Path, PathBuf},
     },
 };
 
+/// Paths of the config files, along with their contents and expanded flag values which errors
+/// borrow from while the config is being applied.
+///
+/// [Config] copies what it keeps, so the files only stay in memory for as long as [Self].
 pub struct PathCache {
     pub config: LazyCell<Option<PathBuf>>,
+    paths: Arena<PathBuf>,
+    strings: Arena<String>,
 }
 impl PathCache {
     pub const fn new() -> Self {
@@ -25,12 +32,15 @@ impl PathCache {
                     config_path
                 })
             }),
+            paths: Arena::new(),
+            strings: Arena::new(),
         }
     }
 
-    pub fn get_config<'a>(&'a self, config: &Config<'a>) -> Option<(&'a Path, PathOrigin)> {
+    pub fn get_config<'a>(&'a self, config: &'a Config) -> Option<(&'a Path, PathOrigin)> {
         config
             .config_file
+            .as_deref()
             .map(|path| (path, PathOrigin::Config))
             .or_else(|| {
                 self.config
@@ -38,6 +48,31 @@ impl PathCache {
                     .map(|path| (path.as_path(), PathOrigin::Default))
             })
     }
+
+    /// Keep `path` alive for as long as [Self].
+    pub fn keep(&self, path: PathBuf) -> &Path {
+        self.paths.alloc(path)
+    }
+
+    /// Keep `string` alive for as long as [Self].
+    pub fn keep_str(&self, string: String) -> &str {
+        self.strings.alloc(string)
+    }
+
+    /// Read the file at `path`, keeping both alive for as long as [Self].
+    pub fn read(&self, path: PathBuf) -> (&Path, io::Result<&str>) {
+        let contents = fs::read_to_string(&path);
+
+        (
+            self.keep(path),
+            contents.map(|contents| self.keep_str(contents)),
+        )
+    }
+
+    /// Every path given to [Self::keep] and [Self::read], in order.
+    pub fn kept(&self) -> Vec<PathBuf> {
+        self.paths.iter().cloned().collect()
+    }
 }
 
 pub enum PathOrigin {
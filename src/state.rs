@@ -1,140 +1,697 @@
 use {
     crate::{
-        backend::{self, Window},
+        backend::{self, Rect, Window},
         config::{
-            key::{Key, KeySequence},
+            key::{
+                InvisibleKey, Key, KeyAction, KeyKind, KeyModifier, KeySequence, KeySide,
+                KeyStrokeKind, MouseKey, ScanCode,
+                trie::{KeyMatch, NodeId},
+            },
+            mode::{KeyTrigger, ModeId},
             Config,
         },
+        layout::{self, Corner, Layout, Placement},
+        supervisor::{ServiceEvent, Supervisor},
+        watcher::Watcher,
     },
     std::{
         collections::{hash_map, HashMap},
-        cmp::Ordering,
         fmt::Display,
         marker::PhantomData,
+        mem,
         sync::mpsc,
+        time::Instant,
     },
 };
 
 pub type EventSender<W, E> = mpsc::Sender<Result<Event<W>, E>>;
 pub type EventReceiver<W, E> = mpsc::Receiver<Result<Event<W>, E>>;
 
-pub struct Storm<'a, S, W, E>
+pub struct Storm<S, W, E>
 where
     E: Display,
     S: backend::State<W, E>,
     W: Window,
 {
     pub backend_state: S,
-    config: Config<'a>,
+    config: Config,
     rx: EventReceiver<W, E>,
     pub workspace: u8,
     pub workspaces: HashMap<u8, Vec<W>>,
+    /// Windows that are placed by hand instead of by [Self::layout], drawn on top of the others.
+    pub floating: HashMap<u8, Vec<W>>,
+    layout: Layout,
+    /// Stops the services once [Storm] is dropped.
+    supervisor: Supervisor,
+    /// Sets [Self::reload] when the config files change, if [Config::watch] is set.
+    watcher: Option<Watcher>,
+    /// Lets the services and [Self::watcher] be replaced after a reload.
+    tx: EventSender<W, E>,
 
-    max_key_binding_len: usize,
-    pressed_keys: KeySequence<'a>,
+    /// The mode whose key bindings are active.
+    mode: ModeId,
+    /// Where [Self::pressed_keys] lead to in the key trie of [Self::mode].
+    pending: NodeId,
+    /// When the pending key sequence gets abandoned.
+    deadline: Option<Instant>,
+    pressed_keys: KeySequence<'static>,
+    /// The last modifier that was pressed, as long as no other key was pressed after it.
+    tapped: Option<(KeyModifier, KeySide)>,
+    /// The last key of the key binding that was executed last, until it gets released.
+    held: Option<HeldKey>,
+    /// Mouse buttons whose press was blocked, since the focused window should not get a release
+    /// without a press either.
+    blocked_buttons: Vec<u8>,
+    /// Where the pointer was last reported to be.
+    pointer: (i16, i16),
+    /// The window that follows the pointer.
+    grab: Option<Grab>,
 
     pub quit: bool,
+    /// Whether to call [Self::reload_config] after the current event.
+    pub reload: bool,
 
     _marker: PhantomData<E>,
 }
-impl<'a, S, W, E> Storm<'a, S, W, E>
+impl<S, W, E> Storm<S, W, E>
 where
-    E: Display,
+    E: Display + Send + 'static,
     S: backend::State<W, E>,
-    W: Window,
+    W: Window + Send + 'static,
 {
-    fn tile_windows(&self) {}
-
-    pub fn new(config: Config<'a>) -> Result<Self, E> {
+    pub fn new(config: Config) -> Result<Self, E> {
         let (tx, rx) = mpsc::channel();
         let mut workspaces = HashMap::new();
 
-        let max_key_binding_len = config.max_key_binding_len();
+        let backend_state = S::new(&mut workspaces, EventSender::clone(&tx))?;
+        let supervisor = Self::spawn_supervisor(&config, &tx);
+        let watcher = Self::spawn_watcher(&config, &tx);
 
         Ok(Self {
-            backend_state: S::new(&mut workspaces, tx)?,
+            backend_state,
             config,
             rx,
             // We start at one since most keyboards have 1 at the top left.
             workspace: 1,
             workspaces,
+            floating: HashMap::new(),
+            layout: Layout::default(),
+            supervisor,
+            watcher,
+            tx,
 
-            max_key_binding_len,
-            pressed_keys: KeySequence::with_capacity(max_key_binding_len),
+            mode: ModeId::DEFAULT,
+            pending: NodeId::ROOT,
+            deadline: None,
+            pressed_keys: KeySequence::new(),
+            tapped: None,
+            held: None,
+            blocked_buttons: Vec::new(),
+            pointer: (0, 0),
+            grab: None,
 
             quit: false,
+            reload: false,
 
             _marker: PhantomData,
         })
     }
 
+    fn spawn_supervisor(config: &Config, tx: &EventSender<W, E>) -> Supervisor {
+        let tx = EventSender::clone(tx);
+        Supervisor::spawn(&config.services, move |event| {
+            let _ = tx.send(Ok(Event::Service(event)));
+        })
+    }
+    fn spawn_watcher(config: &Config, tx: &EventSender<W, E>) -> Option<Watcher> {
+        config.watch.then(|| {
+            let tx = EventSender::clone(tx);
+            Watcher::spawn(config.watched.clone(), move || {
+                let _ = tx.send(Ok(Event::Reload));
+            })
+        })
+    }
+
     pub fn run(mut self) -> Result<(), E> {
         while !self.quit {
-            match self.rx.recv() {
-                Ok(event) => match event {
+            match self.recv() {
+                Ok(Some(event)) => match event {
                     Ok(Event::AddWindow { workspace, window }) => {
-                        match self.workspaces.entry(workspace) {
-                            hash_map::Entry::Occupied(mut entry) => {
-                                entry.get_mut().push(window);
-                            }
-                            hash_map::Entry::Vacant(entry) => {
-                                entry.insert(Vec::from([window]));
+                        let known = |windows: &HashMap<u8, Vec<W>>| {
+                            windows
+                                .get(&workspace)
+                                .is_some_and(|windows| windows.contains(&window))
+                        };
+                        // the same window may be reported again, which should not tile it twice
+                        if !known(&self.workspaces) && !known(&self.floating) {
+                            match self.workspaces.entry(workspace) {
+                                hash_map::Entry::Occupied(mut entry) => {
+                                    entry.get_mut().push(window);
+                                }
+                                hash_map::Entry::Vacant(entry) => {
+                                    entry.insert(Vec::from([window]));
+                                }
                             }
-                        }
 
-                        if workspace == self.workspace {
-                            self.tile_windows();
-                        }
-                    }
-                    Ok(Event::Key(consume, key)) => {
-                        // a response should be sent asap to allow the other thread to continue
-                        self.pressed_keys.push(key);
-                        if self.pressed_keys.len() > self.max_key_binding_len {
-                            let _ = consume.send(KeyIntercept::Allow);
-                            self.pressed_keys.clear();
-                            continue;
+                            if workspace == self.workspace {
+                                self.tile_windows();
+                            }
                         }
-
-                        let mut lesser = false;
-                        if let Some(key_action) =
-                            self.config
-                                .key_bindings
-                                .iter()
-                                .flat_map(|(action, sequences)| {
-                                    sequences.iter().map(move |sequence| (action, sequence))
-                                })
-                                .map(|(action, sequence)| (action, self.pressed_keys.partial_cmp(sequence)))
-                                .inspect(|(_, ord)| if *ord == Some(Ordering::Less) {
-                                    lesser = true;
-                                })
-                                .find(|(_, ord)| *ord == Some(Ordering::Equal))
-                                .map(|(action, _)| action) {
-                                    let _ = consume.send(KeyIntercept::Allow);
-                                    self.pressed_keys.clear();
-
-                                    key_action.execute(&mut self);
-                                } else if lesser {
-                                    let _ = consume.send(KeyIntercept::Block);
-                                } else {
-                                    let _ = consume.send(KeyIntercept::Allow);
-                                    self.pressed_keys.clear();
-                                }
                     }
+                    Ok(Event::Key {
+                        consume,
+                        key,
+                        state,
+                        scan_code,
+                    }) => self.key_event(consume, key, state, scan_code),
+                    Ok(Event::Pointer { x, y }) => self.move_pointer((x, y)),
+                    Ok(Event::Reload) => self.reload = true,
+                    Ok(Event::Service(event)) => match event.is_error() {
+                        true => self.config.error(|f| writeln!(f, "{}", event)),
+                        false => self.config.log(|f| writeln!(f, "{}", event)),
+                    },
                     Err(e) => self
                         .config
                         .error(|f| writeln!(f, "failed to process event: {}", e)),
                 },
+                Ok(None) => self.time_out_keys(),
                 Err(error) => {
                     self.config
                         .error(|f| writeln!(f, "all senders have disconnected: {}", error));
                     break;
                 }
             }
+            if mem::take(&mut self.reload) {
+                self.reload_config();
+            }
             S::each_event(&mut self);
         }
 
         Ok(())
     }
+
+    /// Replace the config with [Config::reload] if it has no errors, keeping the windows and the
+    /// workspaces.
+    ///
+    /// Services only get restarted if they changed, and `--autostart` commands do not run again.
+    pub fn reload_config(&mut self) {
+        let Some(config) = self.config.reload() else {
+            return;
+        };
+        let old = mem::replace(&mut self.config, config);
+
+        if old.services != self.config.services {
+            // stop the old services first, in case they do not allow running more than once
+            self.supervisor.stop();
+            self.supervisor = Self::spawn_supervisor(&self.config, &self.tx);
+        }
+        if old.watch != self.config.watch || old.watched != self.config.watched {
+            self.watcher = None;
+            self.watcher = Self::spawn_watcher(&self.config, &self.tx);
+        }
+
+        // the ids of the old modes could belong to different modes now
+        self.mode = ModeId::DEFAULT;
+        self.reset_pressed_keys();
+        self.tapped = None;
+        // the held key still gets intercepted like before, but the old action is not executed again
+        if let Some(held) = &mut self.held {
+            held.trigger = KeyTrigger::Press;
+        }
+
+        self.config.log(|f| writeln!(f, "reloaded configuration"));
+    }
+}
+impl<S, W, E> Storm<S, W, E>
+where
+    E: Display,
+    S: backend::State<W, E>,
+    W: Window,
+{
+    /// The area that windows get tiled in, which is logged if it cannot be found.
+    fn screen_area(&mut self) -> Option<Rect> {
+        self.backend_state
+            .screen_area()
+            .inspect_err(|err| {
+                self.config
+                    .error(|f| writeln!(f, "failed to get the screen area: {}", err))
+            })
+            .ok()
+    }
+
+    fn tile_windows(&mut self) {
+        let Some(area) = self.screen_area() else {
+            return;
+        };
+        let Some(windows) = self.workspaces.get(&self.workspace) else {
+            return;
+        };
+
+        for (window, rect) in windows.iter().zip(self.layout.tile(area, windows.len())) {
+            if let Err(err) = window.move_to(rect) {
+                self.config
+                    .error(|f| writeln!(f, "failed to move window: {}", err));
+            }
+        }
+    }
+
+    /// Switch the focused window of the current workspace between being tiled and floating.
+    pub fn toggle_floating(&mut self) {
+        let is_focused = |windows: &Vec<W>| windows.iter().position(Window::is_focused);
+        let (tiled, floating) = (
+            self.workspaces.entry(self.workspace).or_default(),
+            self.floating.entry(self.workspace).or_default(),
+        );
+
+        if let Some(index) = is_focused(tiled) {
+            floating.push(tiled.remove(index));
+        } else if let Some(index) = is_focused(floating) {
+            tiled.push(floating.remove(index));
+        } else {
+            return;
+        }
+        self.tile_windows();
+    }
+
+    /// Kill the focused window of the current workspace and tile the windows that are left.
+    pub fn kill_focused(&mut self) {
+        let workspace = self.workspace;
+        let Some(window) = [&mut self.workspaces, &mut self.floating]
+            .into_iter()
+            .find_map(|windows| {
+                let windows = windows.get_mut(&workspace)?;
+                windows
+                    .iter()
+                    .position(Window::is_focused)
+                    .map(|index| windows.remove(index))
+            })
+        else {
+            return;
+        };
+
+        if let Err(err) = window.kill() {
+            self.config
+                .error(|f| writeln!(f, "failed to kill window: {}", err));
+        }
+        self.tile_windows();
+    }
+
+    /// Move or resize the focused floating window of the current workspace by [Config::move_step].
+    pub fn place_floating(&mut self, placement: Placement) {
+        let Some(area) = self.screen_area() else {
+            return;
+        };
+        let Some(window) = self
+            .floating
+            .get(&self.workspace)
+            .and_then(|windows| windows.iter().find(|window| window.is_focused()))
+        else {
+            return;
+        };
+
+        let step = self.config.move_step();
+
+        if let Err(err) = window
+            .position()
+            .and_then(|rect| window.move_to(placement.apply(rect, area, step)))
+        {
+            self.config
+                .error(|f| writeln!(f, "failed to place floating window: {}", err));
+        }
+    }
+
+    /// Make the window under the pointer follow the pointer until the key that executed this gets
+    /// released.
+    ///
+    /// Floating windows are moved or resized from the corner that is closest to the pointer, while
+    /// tiled windows are swapped with other tiled windows or resize the split of [Self::layout].
+    pub fn grab_window(&mut self, kind: GrabKind) {
+        // without a key there is nothing that ends the grab
+        let Some(held) = self.held else {
+            return;
+        };
+        // motion is only reported during grabs, so the last reported position may be stale
+        let pointer = match self.backend_state.pointer() {
+            Ok(pointer) => pointer,
+            Err(err) => {
+                self.config
+                    .error(|f| writeln!(f, "failed to get the pointer position: {}", err));
+                return;
+            }
+        };
+        self.pointer = pointer;
+        let under_pointer =
+            |window: &W| window.position().ok().filter(|rect| rect.contains(pointer));
+
+        let floating = self.floating.get(&self.workspace).and_then(|windows| {
+            // the last floating window is drawn on top
+            windows
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(index, window)| under_pointer(window).map(|rect| (index, rect)))
+        });
+        let target = match floating {
+            Some((index, rect)) => GrabTarget::Floating {
+                index,
+                rect,
+                corner: Corner::nearest(rect, pointer),
+            },
+            None => match self.workspaces.get(&self.workspace).and_then(|windows| {
+                windows
+                    .iter()
+                    .position(|window| under_pointer(window).is_some())
+            }) {
+                Some(index) => GrabTarget::Tiled { index },
+                None => return,
+            },
+        };
+
+        self.grab = Some(Grab {
+            kind,
+            key: held.key,
+            target,
+            origin: pointer,
+        });
+        self.backend_state.track_pointer(true);
+    }
+
+    /// Move the grabbed window along with the pointer.
+    fn move_pointer(&mut self, pointer: (i16, i16)) {
+        self.pointer = pointer;
+        let Some(grab) = self.grab else {
+            return;
+        };
+
+        match grab.target {
+            GrabTarget::Floating {
+                index,
+                rect,
+                corner,
+            } => {
+                let delta = (
+                    i32::from(pointer.0) - i32::from(grab.origin.0),
+                    i32::from(pointer.1) - i32::from(grab.origin.1),
+                );
+                let rect = match grab.kind {
+                    GrabKind::Move => layout::translate(rect, delta),
+                    GrabKind::Resize => layout::resize(rect, corner, delta),
+                };
+
+                if let Some(window) = self
+                    .floating
+                    .get(&self.workspace)
+                    .and_then(|windows| windows.get(index))
+                    && let Err(err) = window.move_to(rect)
+                {
+                    self.config
+                        .error(|f| writeln!(f, "failed to move window: {}", err));
+                }
+            }
+            GrabTarget::Tiled { index } => match grab.kind {
+                GrabKind::Move => {
+                    let Some(area) = self.screen_area() else {
+                        return;
+                    };
+                    let Some(windows) = self.workspaces.get_mut(&self.workspace) else {
+                        return;
+                    };
+                    // windows are swapped by where the layout puts them, not by where they are
+                    let Some(other) = self
+                        .layout
+                        .tile(area, windows.len())
+                        .position(|rect| rect.contains(pointer))
+                        .filter(|&other| other != index && index < windows.len())
+                    else {
+                        return;
+                    };
+
+                    windows.swap(index, other);
+                    self.grab = Some(Grab {
+                        target: GrabTarget::Tiled { index: other },
+                        ..grab
+                    });
+                    self.tile_windows();
+                }
+                GrabKind::Resize => {
+                    let Some(area) = self.screen_area() else {
+                        return;
+                    };
+
+                    self.layout.split_at(area, pointer.0);
+                    self.tile_windows();
+                }
+            },
+        }
+    }
+
+    /// Switch the active key bindings to the ones in `mode`.
+    pub fn set_mode(&mut self, mode: ModeId) {
+        self.mode = mode;
+        self.reset_pressed_keys();
+
+        let name = self.config.modes[mode].name.clone();
+        self.config.log(|f| writeln!(f, "entered mode `{}`", name));
+    }
+
+    fn reset_pressed_keys(&mut self) {
+        self.pending = NodeId::ROOT;
+        self.deadline = None;
+        self.pressed_keys.clear();
+    }
+
+    /// Send keys that were blocked while they were pending to the focused window.
+    fn replay_keys(&mut self, keys: &KeySequence<'_>) {
+        if let Err(err) = self.backend_state.inject_keys(keys) {
+            self.config
+                .error(|f| writeln!(f, "failed to replay keys `{}`: {}", keys, err));
+        }
+    }
+
+    /// Abandon the pending key sequence and execute [Config::timeout_action].
+    fn time_out_keys(&mut self) {
+        let abandoned = mem::take(&mut self.pressed_keys);
+        self.config
+            .log(|f| writeln!(f, "key sequence `{}` timed out", abandoned));
+        self.reset_pressed_keys();
+
+        if self.config.modes[self.mode].unmatched == KeyIntercept::Allow {
+            self.replay_keys(&abandoned);
+        }
+        if let Some(action) = self.config.timeout_action {
+            action.execute(self);
+        }
+    }
+
+    /// Handle a key event from the backend, turning modifiers that were pressed and released by
+    /// themselves into key presses.
+    fn key_event(
+        &mut self,
+        consume: oneshot::Sender<KeyIntercept>,
+        key: Key<'static>,
+        state: KeyState,
+        scan_code: Option<ScanCode>,
+    ) {
+        let modifier = match key.kind() {
+            KeyKind::Invisible(InvisibleKey::Modifier(modifier, side)) => Some((*modifier, *side)),
+            _ => None,
+        };
+        let button = match key.kind() {
+            KeyKind::Mouse(MouseKey::Button(button)) => Some(*button),
+            _ => None,
+        };
+
+        match (state, modifier) {
+            (KeyState::Pressed, Some(_)) => {
+                self.tapped = modifier;
+                let _ = consume.send(KeyIntercept::Allow);
+            }
+            (KeyState::Repeated, Some(_)) => {
+                let _ = consume.send(KeyIntercept::Allow);
+            }
+            (KeyState::Pressed, None) => {
+                self.tapped = None;
+                if self.press_key(consume, key, scan_code) == KeyIntercept::Block {
+                    self.blocked_buttons.extend(button);
+                }
+            }
+            (KeyState::Repeated, None) => self.repeat_key(consume, &key),
+            (KeyState::Released, _) => {
+                match modifier.filter(|_| self.tapped == modifier) {
+                    // the release of a tapped modifier is what presses the tap key
+                    Some((modifier, side)) => {
+                        self.tapped = None;
+
+                        let mut mods = key.mods();
+                        mods.set(modifier, None);
+                        let intercept = self.press_key(
+                            consume,
+                            Key::new(
+                                mods,
+                                KeyKind::Invisible(InvisibleKey::Modifier(modifier, side)),
+                            ),
+                            None,
+                        );
+                        // the modifier would stay held down otherwise
+                        if intercept == KeyIntercept::Block
+                            && let Err(err) = self.backend_state.release_tapped(modifier, side)
+                        {
+                            self.config.error(|f| {
+                                writeln!(f, "failed to release tapped modifier: {}", err)
+                            });
+                        }
+                    }
+                    // releases of keys always reach the focused window, otherwise the key would
+                    // stay held down
+                    None => {
+                        let blocked = button.and_then(|button| {
+                            self.blocked_buttons
+                                .iter()
+                                .position(|&blocked| blocked == button)
+                        });
+                        let _ = consume.send(match blocked {
+                            Some(index) => {
+                                self.blocked_buttons.swap_remove(index);
+                                KeyIntercept::Block
+                            }
+                            None => KeyIntercept::Allow,
+                        });
+                    }
+                }
+                self.release_key(&key);
+            }
+        }
+    }
+
+    /// Reply to an automatic repeat of a key that is held down, which never counts as another key
+    /// press of the pending key sequence.
+    fn repeat_key(&mut self, consume: oneshot::Sender<KeyIntercept>, key: &Key<'_>) {
+        match self.held {
+            Some(held) if held.matches(key) => {
+                let _ = consume.send(held.intercept);
+                if held.trigger == KeyTrigger::Repeat {
+                    held.action.execute(self);
+                }
+            }
+            // the key that is repeating was blocked when it got added to the pending keys
+            _ if self.pending != NodeId::ROOT => {
+                let _ = consume.send(KeyIntercept::Block);
+            }
+            _ => {
+                let _ = consume.send(self.config.modes[self.mode].unmatched(key));
+            }
+        }
+    }
+
+    /// Execute key bindings that wait for their last key to be released.
+    fn release_key(&mut self, key: &Key<'_>) {
+        if self.grab.is_some_and(|grab| last_stroke_is(key, grab.key)) {
+            self.grab = None;
+            self.backend_state.track_pointer(false);
+        }
+        if let Some(held) = self.held.filter(|held| held.matches(key)) {
+            self.held = None;
+            if held.trigger == KeyTrigger::Release {
+                held.action.execute(self);
+            }
+        }
+    }
+
+    /// Reply to a key press from the backend through `consume` and execute its key binding.
+    ///
+    /// Key sequences that use `scan_code` instead of `key` only match if `key` does not.
+    fn press_key(
+        &mut self,
+        consume: oneshot::Sender<KeyIntercept>,
+        key: Key<'static>,
+        scan_code: Option<ScanCode>,
+    ) -> KeyIntercept {
+        let mode = &self.config.modes[self.mode];
+        let physical = scan_code.map(|code| Key::new(key.mods(), KeyKind::Physical(code)));
+        let step = |node| match mode.key_trie.step(node, &key) {
+            KeyMatch::None => physical
+                .as_ref()
+                .map_or(KeyMatch::None, |physical| mode.key_trie.step(node, physical)),
+            step => step,
+        };
+        let (mut abandoned, step) = match step(self.pending) {
+            // the pending keys lead nowhere, but `key` could still start another key sequence
+            KeyMatch::None if self.pending != NodeId::ROOT => {
+                (mem::take(&mut self.pressed_keys), step(NodeId::ROOT))
+            }
+            step => (KeySequence::new(), step),
+        };
+        let (intercept, binding, node) = match step {
+            KeyMatch::Complete(&binding) => {
+                let binding = &mode.key_bindings[binding];
+                (binding.intercept, Some(binding), None)
+            }
+            KeyMatch::Prefix(node) => (KeyIntercept::Block, None, Some(node)),
+            KeyMatch::None => (mode.unmatched(&key), None, None),
+        };
+        let replay = !abandoned.is_empty() && mode.unmatched == KeyIntercept::Allow;
+        // if `key` was let through it would arrive before the replayed keys
+        let replay_key = replay && intercept == KeyIntercept::Allow;
+
+        // a response should be sent asap to allow the other thread to continue
+        let sent = match replay_key {
+            true => KeyIntercept::Block,
+            false => intercept,
+        };
+        let _ = consume.send(sent);
+        self.held = binding
+            .zip(key.strokes().last())
+            .map(|(binding, stroke)| HeldKey {
+                key: stroke.kind(),
+                action: binding.action,
+                intercept,
+                trigger: binding.trigger,
+            });
+        self.reset_pressed_keys();
+
+        if let Some(node) = node {
+            self.pending = node;
+            self.pressed_keys.push(key);
+            self.deadline = self
+                .config
+                .key_timeout
+                .map(|timeout| Instant::now() + timeout);
+        } else if replay_key {
+            abandoned.push(key);
+        }
+        if replay {
+            self.replay_keys(&abandoned);
+        }
+        if let Some(held) = self.held
+            && held.trigger != KeyTrigger::Release
+        {
+            held.action.execute(self);
+        }
+
+        sent
+    }
+
+    /// Wait for the next event, returns none if the pending key sequence timed out first.
+    fn recv(&self) -> Result<Option<Result<Event<W>, E>>, mpsc::RecvError> {
+        match self.deadline {
+            Some(deadline) => {
+                match self
+                    .rx
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                {
+                    Ok(event) => Ok(Some(event)),
+                    Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => Err(mpsc::RecvError),
+                }
+            }
+            None => self.rx.recv().map(Some),
+        }
+    }
+
+    /// Run the autostart commands from the configuration.
+    pub fn autostart(&mut self) {
+        self.config.autostart();
+    }
 }
 
 /// Events to be received in [Storm::run], sent from the platform specific backend.
@@ -144,11 +701,234 @@ pub enum Event<W: Window> {
         workspace: u8,
         window: W,
     },
-    Key(oneshot::Sender<KeyIntercept>, Key<'static>),
+    Key {
+        /// Decides whether the key reaches the focused window.
+        consume: oneshot::Sender<KeyIntercept>,
+        key: Key<'static>,
+        state: KeyState,
+        /// Where the key is on the keyboard, if the backend knows.
+        scan_code: Option<ScanCode>,
+    },
+    /// The pointer moved to `(x, y)` on the screen.
+    Pointer {
+        x: i16,
+        y: i16,
+    },
+    /// One of the watched config files changed.
+    Reload,
+    /// A status change from one of the supervised services.
+    Service(ServiceEvent),
+}
+#[derive(Clone, Copy, Debug)]
+/// The last key of an executed key binding, which decides what happens to its repeats and its
+/// release.
+struct HeldKey {
+    key: KeyStrokeKind,
+    action: KeyAction,
+    intercept: KeyIntercept,
+    trigger: KeyTrigger,
+}
+impl HeldKey {
+    fn matches(&self, key: &Key<'_>) -> bool {
+        last_stroke_is(key, self.key)
+    }
+}
+
+/// Whether the last key press of `key` is `stroke`.
+fn last_stroke_is(key: &Key<'_>, stroke: KeyStrokeKind) -> bool {
+    key.strokes()
+        .last()
+        .is_some_and(|last| last.kind() == stroke)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// What happens to a window while it is grabbed, see [Storm::grab_window].
+pub enum GrabKind {
+    Move,
+    Resize,
+}
+#[derive(Clone, Copy, Debug)]
+/// A window that follows the pointer until [Self::key] gets released.
+struct Grab {
+    kind: GrabKind,
+    key: KeyStrokeKind,
+    target: GrabTarget,
+    /// Where the pointer was when the window got grabbed.
+    origin: (i16, i16),
+}
+#[derive(Clone, Copy, Debug)]
+enum GrabTarget {
+    /// An index into [Storm::floating], with the position the window had when it got grabbed.
+    Floating {
+        index: usize,
+        rect: Rect,
+        corner: Corner,
+    },
+    /// An index into [Storm::workspaces].
+    Tiled { index: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Whether a key was pressed down, repeated because it is held down or let go of.
+pub enum KeyState {
+    Pressed,
+    Repeated,
+    Released,
 }
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum KeyIntercept {
     #[default]
     Allow,
     Block,
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{config::key::KeyModifiers, path_cache::PathCache},
+        std::convert::Infallible,
+    };
+
+    #[derive(PartialEq)]
+    struct TestWindow;
+    impl Window for TestWindow {
+        type Error = Infallible;
+        type String = String;
+
+        fn is_alive(&self) -> bool {
+            true
+        }
+        fn is_focused(&self) -> bool {
+            false
+        }
+        fn is_visible(&self) -> bool {
+            true
+        }
+
+        fn move_to(&self, _: Rect) -> Result<(), Infallible> {
+            Ok(())
+        }
+        fn position(&self) -> Result<Rect, Infallible> {
+            Ok(Rect::default())
+        }
+
+        fn title(&self) -> Result<String, Infallible> {
+            Ok(String::new())
+        }
+
+        fn kill(self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_focus(&mut self, _: bool) -> Result<(), Infallible> {
+            Ok(())
+        }
+        fn set_visibility(&mut self, _: bool) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    /// Records the modifiers that [Storm] releases after they were tapped.
+    #[derive(Default)]
+    struct TestState {
+        released: Vec<(KeyModifier, KeySide)>,
+    }
+    impl backend::State<TestWindow, Infallible> for TestState {
+        fn inject_keys(&mut self, _: &KeySequence<'_>) -> Result<(), Infallible> {
+            Ok(())
+        }
+        fn release_tapped(
+            &mut self,
+            modifier: KeyModifier,
+            side: KeySide,
+        ) -> Result<(), Infallible> {
+            self.released.push((modifier, side));
+            Ok(())
+        }
+        fn new(
+            _: &mut HashMap<u8, Vec<TestWindow>>,
+            _: EventSender<TestWindow, Infallible>,
+        ) -> Result<Self, Infallible> {
+            Ok(Self::default())
+        }
+        fn screen_area(&self) -> Result<Rect, Infallible> {
+            Ok(Rect::default())
+        }
+        fn pointer(&self) -> Result<(i16, i16), Infallible> {
+            Ok((0, 0))
+        }
+    }
+
+    fn storm(args: &[&str]) -> Storm<TestState, TestWindow, Infallible> {
+        let paths = PathCache::new();
+        let mut config = Config::default();
+        config
+            .apply_args(&paths, args.iter().copied().map(Ok::<_, Infallible>))
+            .unwrap();
+        config.build_key_tries().unwrap();
+
+        Storm::new(config).unwrap()
+    }
+
+    /// The intercept that [Storm] replies with to `key` changing to `state`.
+    fn send_key(
+        storm: &mut Storm<TestState, TestWindow, Infallible>,
+        key: Key<'static>,
+        state: KeyState,
+    ) -> KeyIntercept {
+        let (consume, intercept) = oneshot::channel();
+        storm.key_event(consume, key, state, None);
+        intercept.recv().unwrap()
+    }
+
+    #[test]
+    fn tap_binding() {
+        let mut storm = storm(&["-Kquit", "-k<TAP-L>"]);
+        let tap = |modifier, side| {
+            let kind = KeyKind::Invisible(InvisibleKey::Modifier(modifier, side));
+            let held = KeyModifiers::from_iter([(modifier, side)]);
+            let press = Key::new(KeyModifiers::default(), kind.clone());
+            [
+                (press, KeyState::Pressed),
+                (Key::new(held, kind), KeyState::Released),
+            ]
+        };
+
+        // the release of a tap binding is blocked, and released again by the backend
+        let intercepts = tap(KeyModifier::Super, KeySide::Left)
+            .map(|(key, state)| send_key(&mut storm, key, state));
+        assert_eq!(intercepts, [KeyIntercept::Allow, KeyIntercept::Block]);
+        assert_eq!(
+            storm.backend_state.released,
+            [(KeyModifier::Super, KeySide::Left)]
+        );
+        assert!(storm.quit);
+
+        // the release of a tapped modifier without a binding reaches the focused window
+        storm.backend_state.released.clear();
+        let intercepts = tap(KeyModifier::Alt, KeySide::Left)
+            .map(|(key, state)| send_key(&mut storm, key, state));
+        assert_eq!(intercepts, [KeyIntercept::Allow, KeyIntercept::Allow]);
+        assert_eq!(storm.backend_state.released, []);
+    }
+
+    #[test]
+    fn blocked_button_release() {
+        let mut storm = storm(&["-Kquit", "-kL-<MB-1>"]);
+        let click = |mods, button| {
+            let kind = KeyKind::Mouse(MouseKey::Button(button));
+            [KeyState::Pressed, KeyState::Released]
+                .map(|state| (Key::new(mods, kind.clone()), state))
+        };
+        let super_held = KeyModifiers::from_iter([(KeyModifier::Super, KeySide::Left)]);
+
+        // the focused window gets neither the press nor the release of a bound button
+        let intercepts = click(super_held, 1).map(|(key, state)| send_key(&mut storm, key, state));
+        assert_eq!(intercepts, [KeyIntercept::Block, KeyIntercept::Block]);
+
+        let intercepts =
+            click(KeyModifiers::default(), 1).map(|(key, state)| send_key(&mut storm, key, state));
+        assert_eq!(intercepts, [KeyIntercept::Allow, KeyIntercept::Allow]);
+    }
+}
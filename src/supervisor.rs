@@ -0,0 +1,327 @@
+//! Keeps [Service]s alive in a background thread.
+
+use {
+    crate::config::command::{RestartPolicy, Service},
+    std::{
+        fmt::{self, Display, Formatter},
+        io,
+        process::{self, Child, ExitStatus},
+        sync::mpsc,
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often the children are checked for exits.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// The delay before the first restart.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// The delay is doubled on every restart until it reaches this.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Services that stayed alive for this long have their backoff reset.
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// Handle to the supervisor thread, which stops every service when dropped.
+pub struct Supervisor {
+    stop: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Supervisor {
+    /// Start every service in a new thread, reporting their status through `report`.
+    pub fn spawn<'a, I, F>(services: I, mut report: F) -> Self
+    where
+        I: IntoIterator<Item = &'a Service>,
+        F: FnMut(ServiceEvent) + Send + 'static,
+    {
+        let services = services
+            .into_iter()
+            .filter_map(|service| {
+                let name = service.command.to_string();
+                match service.command.to_process() {
+                    Ok(process) => Some(Supervised::new(name, process, service.restart)),
+                    Err(err) => {
+                        report(ServiceEvent {
+                            name,
+                            status: ServiceStatus::SpawnFailed(err),
+                        });
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let (stop, rx) = mpsc::channel();
+
+        Self {
+            stop,
+            thread: (!services.is_empty())
+                .then(move || thread::spawn(move || Self::supervise(services, rx, report))),
+        }
+    }
+
+    fn supervise<F>(mut services: Vec<Supervised>, stop: mpsc::Receiver<()>, mut report: F)
+    where
+        F: FnMut(ServiceEvent),
+    {
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(POLL_INTERVAL) {
+            let now = Instant::now();
+            services
+                .iter_mut()
+                .for_each(|service| service.poll(now, &mut report));
+        }
+
+        services
+            .into_iter()
+            .for_each(|service| service.stop(&mut report));
+    }
+
+    /// Stop every service and wait for the thread to end.
+    pub fn stop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Debug)]
+/// Decides how long to wait before restarting a service, which doubles every time it exits
+/// without staying alive for [STABLE_AFTER].
+struct Backoff {
+    restart: RestartPolicy,
+    delay: Duration,
+}
+impl Backoff {
+    const fn new(restart: RestartPolicy) -> Self {
+        Self {
+            restart,
+            delay: MIN_BACKOFF,
+        }
+    }
+
+    /// The delay before restarting a service that exited after running for `uptime`, or none if
+    /// it should not be restarted.
+    fn exited(&mut self, uptime: Duration, success: bool) -> Option<Duration> {
+        if uptime >= STABLE_AFTER {
+            self.delay = MIN_BACKOFF;
+        }
+        if !self.restart.should_restart(success) {
+            return None;
+        }
+
+        let delay = self.delay;
+        self.delay = (delay * 2).min(MAX_BACKOFF);
+        Some(delay)
+    }
+}
+
+struct Supervised {
+    name: String,
+    process: process::Command,
+    backoff: Backoff,
+
+    child: Option<Child>,
+    started: Instant,
+    /// When [Self::child] should be spawned again.
+    restart_at: Option<Instant>,
+}
+impl Supervised {
+    fn new(name: String, process: process::Command, restart: RestartPolicy) -> Self {
+        let now = Instant::now();
+
+        Self {
+            name,
+            process,
+            backoff: Backoff::new(restart),
+
+            child: None,
+            started: now,
+            restart_at: Some(now),
+        }
+    }
+
+    fn report<F: FnMut(ServiceEvent)>(&self, report: &mut F, status: ServiceStatus) {
+        report(ServiceEvent {
+            name: self.name.clone(),
+            status,
+        })
+    }
+
+    /// Schedule a restart if [Self::backoff] allows it, after the service exited at `now`.
+    fn exited<F: FnMut(ServiceEvent)>(&mut self, now: Instant, success: bool, report: &mut F) {
+        if let Some(delay) = self
+            .backoff
+            .exited(now.duration_since(self.started), success)
+        {
+            self.restart_at = Some(now + delay);
+            self.report(report, ServiceStatus::Restarting(delay));
+        }
+    }
+
+    fn poll<F: FnMut(ServiceEvent)>(&mut self, now: Instant, report: &mut F) {
+        if let Some(child) = &mut self.child {
+            let success = match child.try_wait() {
+                Ok(Some(status)) => {
+                    self.child = None;
+                    self.report(report, ServiceStatus::Exited(status));
+                    status.success()
+                }
+                Ok(None) => return,
+                // the child is lost if it cannot be waited on, so it counts as a failed exit
+                Err(err) => {
+                    let _ = child.kill();
+                    self.child = None;
+                    self.report(report, ServiceStatus::WaitFailed(err));
+                    false
+                }
+            };
+            self.exited(now, success, report);
+        } else if self.restart_at.is_some_and(|restart_at| now >= restart_at) {
+            self.restart_at = None;
+
+            match self.process.spawn() {
+                Ok(child) => {
+                    self.child = Some(child);
+                    self.started = now;
+                    self.report(report, ServiceStatus::Started);
+                }
+                Err(err) => {
+                    self.started = now;
+                    self.report(report, ServiceStatus::SpawnFailed(err));
+                    self.exited(now, false, report);
+                }
+            }
+        }
+    }
+
+    fn stop<F: FnMut(ServiceEvent)>(mut self, report: &mut F) {
+        if let Some(mut child) = self.child.take() {
+            if let Err(err) = child.kill().and_then(|_| child.wait()) {
+                self.report(report, ServiceStatus::WaitFailed(err));
+            } else {
+                self.report(report, ServiceStatus::Stopped);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ServiceEvent {
+    pub name: String,
+    pub status: ServiceStatus,
+}
+impl ServiceEvent {
+    /// Whether or not this event should be logged as an error.
+    pub fn is_error(&self) -> bool {
+        match &self.status {
+            ServiceStatus::Exited(status) => !status.success(),
+            ServiceStatus::SpawnFailed(_) | ServiceStatus::WaitFailed(_) => true,
+            ServiceStatus::Started | ServiceStatus::Restarting(_) | ServiceStatus::Stopped => false,
+        }
+    }
+}
+impl Display for ServiceEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "service `{}` ", self.name)?;
+
+        match &self.status {
+            ServiceStatus::Started => write!(f, "started"),
+            ServiceStatus::Exited(status) => write!(f, "exited: {}", status),
+            ServiceStatus::Restarting(delay) => {
+                write!(f, "restarting in {} seconds", delay.as_secs_f32())
+            }
+            ServiceStatus::SpawnFailed(err) => write!(f, "failed to start: {}", err),
+            ServiceStatus::WaitFailed(err) => write!(f, "failed to get status: {}", err),
+            ServiceStatus::Stopped => write!(f, "stopped"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ServiceStatus {
+    Started,
+    Exited(ExitStatus),
+    /// The service will restart after the delay.
+    Restarting(Duration),
+    SpawnFailed(io::Error),
+    WaitFailed(io::Error),
+    /// Killed because the window manager is exiting.
+    Stopped,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{env, process::Stdio},
+    };
+
+    #[test]
+    fn backoff() {
+        let secs = Duration::from_secs;
+        let mut backoff = Backoff::new(RestartPolicy::Always);
+
+        assert_eq!(
+            (0..8)
+                .map(|_| backoff.exited(Duration::ZERO, false))
+                .collect::<Vec<_>>(),
+            [1, 2, 4, 8, 16, 32, 60, 60].map(|delay| Some(secs(delay)))
+        );
+        // services that stayed alive for long enough start over
+        assert_eq!(backoff.exited(STABLE_AFTER, true), Some(MIN_BACKOFF));
+        assert_eq!(backoff.exited(secs(1), true), Some(secs(2)));
+
+        let mut backoff = Backoff::new(RestartPolicy::OnFailure);
+        assert_eq!(backoff.exited(Duration::ZERO, true), None);
+        assert_eq!(backoff.exited(Duration::ZERO, false), Some(MIN_BACKOFF));
+
+        let mut backoff = Backoff::new(RestartPolicy::Never);
+        assert_eq!(backoff.exited(Duration::ZERO, false), None);
+    }
+
+    #[test]
+    fn supervised_restart() {
+        // the test binary itself, which exits successfully after listing the tests
+        let mut process = process::Command::new(env::current_exe().unwrap());
+        process
+            .arg("--list")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let mut service = Supervised::new("tests".to_owned(), process, RestartPolicy::Always);
+
+        let mut events = Vec::new();
+        let mut report = |event: ServiceEvent| events.push(event.status);
+        let start = Instant::now();
+
+        service.poll(start, &mut report);
+        // the exit is waited for in real time, while the restart follows the given clock
+        while service.child.is_some() {
+            thread::sleep(Duration::from_millis(10));
+            service.poll(start, &mut report);
+        }
+        service.poll(start + MIN_BACKOFF / 2, &mut report);
+        assert!(service.child.is_none());
+        service.poll(start + MIN_BACKOFF, &mut report);
+        assert!(service.child.is_some());
+        service.stop(&mut report);
+
+        assert!(
+            matches!(
+                events.as_slice(),
+                [
+                    ServiceStatus::Started,
+                    ServiceStatus::Exited(status),
+                    ServiceStatus::Restarting(MIN_BACKOFF),
+                    ServiceStatus::Started,
+                    ServiceStatus::Stopped,
+                ] if status.success()
+            ),
+            "{:?}",
+            events
+        );
+    }
+}
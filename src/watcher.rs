@@ -0,0 +1,64 @@
+//! Notices changes to config files in a background thread.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+/// How often the files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Handle to the watcher thread, which stops when dropped.
+pub struct Watcher {
+    stop: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Watcher {
+    /// Call `changed` whenever one of `paths` is created, removed or modified.
+    pub fn spawn<F>(paths: Vec<PathBuf>, mut changed: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let (stop, rx) = mpsc::channel();
+
+        Self {
+            stop,
+            thread: (!paths.is_empty()).then(move || {
+                thread::spawn(move || {
+                    let mut last = modified(&paths);
+                    while let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(POLL_INTERVAL)
+                    {
+                        let next = modified(&paths);
+                        if next != last {
+                            last = next;
+                            changed();
+                        }
+                    }
+                })
+            }),
+        }
+    }
+}
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// When each of `paths` was last modified, which is none for paths that do not exist.
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}